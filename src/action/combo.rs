@@ -0,0 +1,367 @@
+use std::{cmp::Reverse, time::Duration};
+
+use crate::{
+    device::DeviceInput,
+    input::{Input, InputState},
+    timer::TimerId,
+};
+
+use super::{ActionExecutor, Timer};
+
+/// Presses of inputs that can be part of a combo, held back until the combo
+/// is completed or can no longer be
+#[derive(Debug)]
+pub(super) struct PendingCombo {
+    events: Vec<DeviceInput>,
+    timer: TimerId,
+}
+impl PendingCombo {
+    fn contains(&self, input: Input) -> bool {
+        self.events
+            .iter()
+            .any(|event| event.input_event().input() == input)
+    }
+}
+
+/// A completed combo, some of whose inputs are still held down
+#[derive(Debug)]
+pub(super) struct ActiveCombo {
+    action: usize,
+    /// The inputs of the combo that have not been released yet
+    held: Vec<Input>,
+    /// Whether the action has been released, which happens as soon as any
+    /// input of the combo is released
+    released: bool,
+}
+
+/// A combo of a device, by the index of its action, and its inputs
+type Combo = (usize, Vec<Input>);
+
+/// What becomes of a press held back for a combo once the combo is resolved
+#[derive(Debug, PartialEq)]
+enum ComboStep {
+    /// The press is handled as if there was no combo
+    Replay(usize),
+    /// The press completes the combo
+    Complete(usize),
+}
+
+/// The combos that the pressed inputs can still be part of
+fn combo_candidates(combos: &[Combo], pressed: &[Input]) -> Vec<Combo> {
+    combos
+        .iter()
+        .filter(|(_, inputs)| pressed.iter().all(|input| inputs.contains(input)))
+        .cloned()
+        .collect()
+}
+
+/// The combo the pressed inputs complete. While more inputs can still be
+/// pressed, a combo is only completed if no longer combo can still be;
+/// once `done`, the longest combo whose inputs are all pressed is.
+fn completed_combo<'a>(combos: &'a [Combo], pressed: &[Input], done: bool) -> Option<&'a Combo> {
+    let longer = combos.iter().any(|(_, inputs)| {
+        inputs.len() > pressed.len() && pressed.iter().all(|input| inputs.contains(input))
+    });
+
+    if longer && !done {
+        return None;
+    }
+
+    combos
+        .iter()
+        .filter(|(_, inputs)| inputs.iter().all(|input| pressed.contains(input)))
+        .min_by_key(|(_, inputs)| Reverse(inputs.len()))
+}
+
+/// The order the held back presses are handled in once the combo with the
+/// inputs is completed, if any: the presses that are not part of it are
+/// replayed around the last press of the combo, which completes it
+fn completion_order(pressed: &[Input], inputs: &[Input]) -> Vec<ComboStep> {
+    let last = pressed.iter().rposition(|input| inputs.contains(input));
+
+    pressed
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, input)| {
+            if !inputs.contains(input) {
+                Some(ComboStep::Replay(idx))
+            } else if Some(idx) == last {
+                Some(ComboStep::Complete(idx))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+impl ActionExecutor {
+    /// The combos of the device of an input in its active layers whose guards
+    /// hold
+    fn combos(&self, input: &DeviceInput) -> Vec<Combo> {
+        let device = input.device();
+
+        let (Some(actions), Some(state)) = (self.actions.get(device), self.states.get(device))
+        else {
+            return vec![];
+        };
+
+        let source = self.device_names(input.source());
+
+        actions
+            .iter()
+            .enumerate()
+            .filter(|(_, action)| {
+                action.combo
+                    && state.is_layer_active(action.layer.as_ref())
+                    && action.listens_to(device, &source)
+                    && self.input_values.allow(device, action, &self.names)
+            })
+            .map(|(idx, action)| (idx, action.combo_inputs()))
+            .collect()
+    }
+
+    /// Holds back presses of inputs that can be part of a combo until either
+    /// every input of the combo is pressed, running its action instead of
+    /// theirs, or the combo term expires. A combo that is part of a longer one
+    /// is only completed once the longer one can no longer be. Events of the
+    /// inputs of a completed combo only reach the combo.
+    pub(super) fn hold_combo(&mut self, input: &DeviceInput) -> bool {
+        let device = input.device();
+        let event = input.input_event();
+
+        if !event.input().is_toggle() || !self.actions.contains_key(device) {
+            return false;
+        }
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        let active = state
+            .active_combos
+            .iter()
+            .position(|combo| combo.held.contains(&event.input()));
+
+        let pending = state
+            .combo
+            .as_ref()
+            .is_some_and(|pending| pending.contains(event.input()));
+
+        match InputState::from_i32(event.state()) {
+            Some(InputState::Pressed) => {}
+            Some(InputState::Repeated) => return active.is_some() || pending,
+            Some(InputState::Released) | None => {
+                if let Some(idx) = active {
+                    let combo = &mut state.active_combos[idx];
+                    combo.held.retain(|&held| held != event.input());
+
+                    let action = combo.action;
+                    let release = !std::mem::replace(&mut combo.released, true);
+
+                    if combo.held.is_empty() {
+                        state.active_combos.remove(idx);
+                    }
+
+                    if release {
+                        self.dispatch(input, &[action]);
+                    }
+
+                    return true;
+                }
+
+                // Released before a longer combo was completed, the release
+                // reaches the combo completed so far if any
+                if pending {
+                    self.flush_combo(device);
+                    return self.hold_combo(input);
+                }
+
+                return false;
+            }
+        }
+
+        let mut pressed: Vec<Input> = state
+            .combo
+            .iter()
+            .flat_map(|pending| &pending.events)
+            .map(|event| event.input_event().input())
+            .collect();
+        pressed.push(event.input());
+
+        let holding = state.combo.is_some();
+
+        let candidates = combo_candidates(&self.combos(input), &pressed);
+
+        if candidates.is_empty() {
+            if !holding {
+                return false;
+            }
+
+            // The press may still start a combo of its own
+            self.flush_combo(device);
+            return self.hold_combo(input);
+        }
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        if let Some(&(action, _)) = completed_combo(&candidates, &pressed, false) {
+            if let Some(pending) = state.combo.take() {
+                self.scheduler.cancel(pending.timer);
+                self.timers.remove(&pending.timer);
+            }
+
+            state.active_combos.push(ActiveCombo {
+                action,
+                held: pressed,
+                released: false,
+            });
+
+            self.dispatch(input, &[action]);
+            return true;
+        }
+
+        match &mut state.combo {
+            Some(pending) => pending.events.push(input.clone()),
+            None => {
+                let actions = &self.actions[device];
+
+                let combo_term = candidates
+                    .iter()
+                    .map(|&(idx, _)| actions[idx].combo_term)
+                    .max()
+                    .unwrap_or_default();
+
+                let timer = self.scheduler.schedule(Duration::from_millis(combo_term));
+                self.timers.insert(timer, Timer::Combo(device.to_string()));
+
+                state.combo = Some(PendingCombo {
+                    events: vec![input.clone()],
+                    timer,
+                });
+            }
+        }
+
+        true
+    }
+
+    /// Resolves the pending combo of a device once no more inputs can be
+    /// pressed for it, completing the longest combo pressed so far and
+    /// replaying the other presses it held back
+    pub(super) fn flush_combo(&mut self, device: &str) {
+        let Some(pending) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.combo.take())
+        else {
+            return;
+        };
+
+        self.scheduler.cancel(pending.timer);
+        self.timers.remove(&pending.timer);
+
+        let pressed: Vec<Input> = pending
+            .events
+            .iter()
+            .map(|event| event.input_event().input())
+            .collect();
+
+        let combos = match pending.events.first() {
+            Some(event) => self.combos(event),
+            None => vec![],
+        };
+
+        let completed = completed_combo(&combos, &pressed, true).cloned();
+
+        let inputs = completed
+            .as_ref()
+            .map(|(_, inputs)| inputs.as_slice())
+            .unwrap_or_default();
+
+        for step in completion_order(&pressed, inputs) {
+            match step {
+                ComboStep::Replay(idx) => self.process_input(pending.events[idx].clone()),
+                ComboStep::Complete(idx) => {
+                    let Some((action, inputs)) = completed.clone() else {
+                        continue;
+                    };
+
+                    self.states
+                        .entry(device.to_string())
+                        .or_default()
+                        .active_combos
+                        .push(ActiveCombo {
+                            action,
+                            held: inputs,
+                            released: false,
+                        });
+
+                    self.dispatch(&pending.events[idx], &[action]);
+                }
+            }
+        }
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::input::Key;
+
+    #[test]
+    fn combo_candidates_contain_pressed() {
+        let (j, k, l) = (Input::Key(Key::J), Input::Key(Key::K), Input::Key(Key::L));
+
+        let combos = vec![(0, vec![j, k]), (1, vec![k, l]), (2, vec![j, k, l])];
+
+        assert_eq!(combos, combo_candidates(&combos, &[k]));
+        assert_eq!(
+            vec![(0, vec![j, k]), (2, vec![j, k, l])],
+            combo_candidates(&combos, &[k, j])
+        );
+        assert!(combo_candidates(&combos, &[j, Input::Key(Key::X)]).is_empty());
+    }
+
+    #[test]
+    fn overlapping_combos_wait_for_longest() {
+        let (j, k, l) = (Input::Key(Key::J), Input::Key(Key::K), Input::Key(Key::L));
+
+        let combos = vec![(0, vec![j, k]), (1, vec![j, k, l])];
+
+        // j+k+l can still be completed
+        assert_eq!(None, completed_combo(&combos, &[j, k], false));
+        assert_eq!(
+            Some(&(1, vec![j, k, l])),
+            completed_combo(&combos, &[j, k, l], false)
+        );
+
+        // The term expired or another input was pressed or released
+        assert_eq!(
+            Some(&(0, vec![j, k])),
+            completed_combo(&combos, &[j, k], true)
+        );
+        assert_eq!(None, completed_combo(&combos, &[j], true));
+
+        // Without a longer combo j+k completes right away
+        assert_eq!(
+            Some(&(0, vec![j, k])),
+            completed_combo(&combos[..1], &[k, j], false)
+        );
+    }
+
+    #[test]
+    fn combo_completion_order() {
+        let (j, k, l) = (Input::Key(Key::J), Input::Key(Key::K), Input::Key(Key::L));
+
+        assert_eq!(
+            vec![ComboStep::Complete(1), ComboStep::Replay(2)],
+            completion_order(&[j, k, l], &[j, k])
+        );
+        assert_eq!(
+            vec![ComboStep::Replay(0), ComboStep::Complete(2)],
+            completion_order(&[l, j, k], &[j, k])
+        );
+        assert_eq!(
+            vec![ComboStep::Replay(0), ComboStep::Replay(1)],
+            completion_order(&[j, k], &[])
+        );
+    }
+}
@@ -0,0 +1,375 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    config::{ActionType, BindMode, Gesture},
+    device::DeviceInput,
+    input::{Input, InputState},
+    timer::TimerId,
+};
+
+use super::{ActionExecutor, HookContext, Timer};
+
+/// A multi-tap or long-press sequence of an input that has not yet been
+/// recognised
+#[derive(Debug)]
+pub(super) struct PendingGesture {
+    pub input: Input,
+    /// The actions triggered by the first press of the sequence
+    pub triggered: Vec<usize>,
+    pub taps: u8,
+    pub pressed: bool,
+    /// Long-press actions run during the current press
+    pub long_pressed: Vec<usize>,
+    /// Presses and releases of the input, replayed if no gesture matches
+    pub events: Vec<DeviceInput>,
+    pub timers: Vec<TimerId>,
+}
+
+impl ActionExecutor {
+    /// Defers the presses and releases of an input that has multi-tap or
+    /// long-press actions until the performed gesture has been recognised, so
+    /// that a completed sequence does not also run the single press actions
+    pub(super) fn defer_gesture(&mut self, input: &DeviceInput, triggered: &[usize]) -> bool {
+        let device = input.device();
+        let event = input.input_event();
+
+        if !event.input().is_toggle() {
+            return false;
+        }
+
+        let input_state = InputState::from_i32(event.state());
+
+        let pending_input = self
+            .states
+            .get(device)
+            .and_then(|state| state.gesture.as_ref())
+            .map(|gesture| gesture.input);
+
+        match pending_input {
+            // Pressing another input ends the sequence
+            Some(pending_input) if pending_input != event.input() => {
+                if input_state == Some(InputState::Pressed) {
+                    self.resolve_gesture(device);
+                }
+
+                false
+            }
+            Some(_) => {
+                self.continue_gesture(input);
+
+                true
+            }
+            None if input_state == Some(InputState::Pressed) => {
+                self.start_gesture(input, triggered)
+            }
+            None => false,
+        }
+    }
+
+    fn start_gesture(&mut self, input: &DeviceInput, triggered: &[usize]) -> bool {
+        let device = input.device();
+        let event = input.input_event();
+
+        let Some(actions) = self.actions.get(device) else {
+            return false;
+        };
+
+        let gestures = triggered
+            .iter()
+            .filter_map(|&idx| Some((idx, actions[idx].action.gesture()?)))
+            .collect::<Vec<_>>();
+
+        if gestures.is_empty() {
+            return false;
+        }
+
+        let timers = gestures
+            .into_iter()
+            .filter_map(|(idx, gesture)| match gesture {
+                Gesture::Hold(ms) => {
+                    let timer = self.scheduler.schedule(Duration::from_millis(ms));
+                    self.timers
+                        .insert(timer, Timer::GestureHold(device.to_string(), idx));
+
+                    Some(timer)
+                }
+                _ => None,
+            })
+            .collect();
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        state.gesture = Some(PendingGesture {
+            input: event.input(),
+            triggered: triggered.to_vec(),
+            taps: 1,
+            pressed: true,
+            long_pressed: vec![],
+            events: vec![input.clone()],
+            timers,
+        });
+
+        true
+    }
+
+    fn continue_gesture(&mut self, input: &DeviceInput) {
+        let device = input.device();
+        let event = input.input_event();
+
+        let Some(actions) = self.actions.get(device) else {
+            return;
+        };
+
+        let Some(pending) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.gesture.as_mut())
+        else {
+            return;
+        };
+
+        match InputState::from_i32(event.state()) {
+            Some(InputState::Pressed) => {
+                for timer in pending.timers.drain(..) {
+                    self.scheduler.cancel(timer);
+                    self.timers.remove(&timer);
+                }
+
+                pending.taps += 1;
+                pending.pressed = true;
+                pending.events.push(input.clone());
+            }
+            Some(InputState::Released) => {
+                for timer in pending.timers.drain(..) {
+                    self.scheduler.cancel(timer);
+                    self.timers.remove(&timer);
+                }
+
+                pending.pressed = false;
+                pending.events.push(input.clone());
+
+                // A long press ends the sequence once released
+                if !pending.long_pressed.is_empty() {
+                    let long_pressed = std::mem::take(&mut pending.long_pressed);
+
+                    if let Some(state) = self.states.get_mut(device) {
+                        state.gesture = None;
+                    }
+
+                    for idx in long_pressed {
+                        self.run_gesture(device, idx, InputState::Released);
+                    }
+
+                    return;
+                }
+
+                let most_taps = pending
+                    .triggered
+                    .iter()
+                    .filter_map(|&idx| actions[idx].action.gesture()?.taps())
+                    .max()
+                    .unwrap_or(1);
+
+                // The next tap has to follow within the longest window of
+                // the multi-tap actions
+                let window = pending
+                    .triggered
+                    .iter()
+                    .filter_map(|&idx| actions[idx].action.gesture()?.window())
+                    .max()
+                    .unwrap_or(Gesture::tap_window());
+
+                if pending.taps >= most_taps {
+                    self.resolve_gesture(device);
+                } else {
+                    let timer = self.scheduler.schedule(Duration::from_millis(window));
+                    self.timers
+                        .insert(timer, Timer::GestureWindow(device.to_string()));
+
+                    pending.timers.push(timer);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs a long-press action whose input has been held long enough
+    pub(super) fn run_long_press(&mut self, device: &str, action: usize) {
+        let Some(pending) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.gesture.as_mut())
+        else {
+            return;
+        };
+
+        if !pending.pressed {
+            return;
+        }
+
+        pending.long_pressed.push(action);
+
+        self.run_gesture(device, action, InputState::Pressed);
+    }
+
+    /// Ends the pending sequence of a device, running the multi-tap actions
+    /// matching the number of taps. If there are none the deferred presses
+    /// and releases are replayed to the other actions of the input.
+    pub(super) fn resolve_gesture(&mut self, device: &str) {
+        let Some(actions) = self.actions.get(device) else {
+            return;
+        };
+
+        let Some(state) = self.states.get_mut(device) else {
+            return;
+        };
+
+        let Some(pending) = state.gesture.take() else {
+            return;
+        };
+
+        for timer in &pending.timers {
+            self.scheduler.cancel(*timer);
+            self.timers.remove(timer);
+        }
+
+        let multi_taps = pending
+            .triggered
+            .iter()
+            .copied()
+            .filter(|&idx| {
+                actions[idx]
+                    .action
+                    .gesture()
+                    .and_then(|gesture| gesture.taps())
+                    == Some(pending.taps)
+            })
+            .collect::<Vec<_>>();
+
+        if multi_taps.is_empty() {
+            for input in pending.events {
+                self.dispatch(&input, &pending.triggered);
+            }
+
+            return;
+        }
+
+        // The release of an input still held down must not reach its single
+        // press actions, it releases the multi-tap actions instead
+        let held = match pending.events.first() {
+            Some(event) if pending.pressed => Some((event.source().to_string(), pending.input)),
+            _ => None,
+        };
+
+        if let Some(held) = &held {
+            state.triggered.remove(held);
+            state.multi_tapped.insert(held.clone(), multi_taps.clone());
+        }
+
+        for idx in multi_taps {
+            self.run_gesture(device, idx, InputState::Pressed);
+
+            if held.is_none() {
+                self.run_gesture(device, idx, InputState::Released);
+            }
+        }
+    }
+
+    /// Releases the multi-tap actions whose last tap is released, swallowing
+    /// the repeats and release of the tap
+    pub(super) fn release_multi_tap(&mut self, input: &DeviceInput) -> bool {
+        let device = input.device();
+        let event = input.input_event();
+
+        let Some(state) = self.states.get_mut(device) else {
+            return false;
+        };
+
+        let key = (input.source().to_string(), event.input());
+
+        match InputState::from_i32(event.state()) {
+            Some(InputState::Repeated) => state.multi_tapped.contains_key(&key),
+            Some(InputState::Released) => {
+                let Some(multi_taps) = state.multi_tapped.remove(&key) else {
+                    return false;
+                };
+
+                for idx in multi_taps {
+                    self.run_gesture(device, idx, InputState::Released);
+                }
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn run_gesture(&mut self, device: &str, idx: usize, input_state: InputState) {
+        let Some(action) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(idx))
+        else {
+            return;
+        };
+
+        let output = self.outputs.find(action.output.as_deref());
+
+        match action.action {
+            ActionType::Hook { ref hook, .. } => {
+                if input_state == InputState::Pressed {
+                    let context = HookContext {
+                        device: device.to_string(),
+                        input: action.bind.trigger,
+                        value: input_state.as_i32(),
+                        timestamp: SystemTime::now(),
+                    };
+
+                    self.run_hook(idx, context);
+                } else if hook.kill_on_release {
+                    self.kill_hook(device, idx);
+                }
+            }
+            ActionType::Print { ref print, .. } => {
+                if input_state == InputState::Pressed {
+                    Self::execute_print(self.outputs.device(output), self.layout, print);
+                }
+            }
+            ActionType::Bind {
+                to,
+                mode: BindMode::Toggle,
+                ..
+            } if to.is_toggle() => {
+                if input_state == InputState::Pressed {
+                    let state = self.states.entry(device.to_string()).or_default();
+
+                    Self::toggle_latch(&mut self.outputs, state, output, to);
+                }
+            }
+            ActionType::Bind {
+                to,
+                mode: BindMode::Turbo,
+                ..
+            } if to.is_toggle() => {
+                self.set_turbo(device, idx, input_state == InputState::Pressed);
+            }
+            ActionType::Bind { to, .. } if !to.is_toggle() => {
+                self.set_key_axis(device, idx, input_state == InputState::Pressed);
+            }
+            ActionType::Bind { to, .. } => {
+                Self::emit(self.outputs.device(output), to, input_state.as_i32())
+            }
+            ActionType::Macro { .. } => {
+                if input_state == InputState::Pressed {
+                    self.trigger_macro(device, idx);
+                }
+            }
+            ActionType::TapHold { .. } | ActionType::Pointer { .. } | ActionType::Zones { .. } => {}
+            ActionType::Layer { ref layer, .. } => {
+                if let Some(state) = self.states.get_mut(device) {
+                    state.switch_layer(layer, input_state);
+                }
+            }
+        }
+    }
+}
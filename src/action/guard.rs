@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use crate::{
+    config::Action,
+    device::{DeviceId, DeviceInput, DeviceNames},
+    input::Input,
+};
+
+/// The last value of every input of every watched device, whether or not it
+/// reached an action, for the guards of actions
+#[derive(Debug, Default)]
+pub(super) struct InputValues(HashMap<DeviceId, HashMap<Input, i32>>);
+impl InputValues {
+    /// Records the value of a key, button or absolute axis. Relative axes
+    /// only report motion, they cannot be guarded on.
+    pub fn update(&mut self, input: &DeviceInput) {
+        let event = input.input_event();
+
+        if let Input::RelAxis(_) = event.input() {
+            return;
+        }
+
+        self.0
+            .entry(input.device().to_string())
+            .or_default()
+            .insert(event.input(), event.state());
+    }
+
+    /// The keys and buttons held down on a device
+    pub fn held(&self, device: &str) -> Vec<Input> {
+        self.0
+            .get(device)
+            .into_iter()
+            .flatten()
+            .filter(|&(input, &value)| input.is_toggle() && value != 0)
+            .map(|(&input, _)| input)
+            .collect()
+    }
+
+    pub fn remove_device(&mut self, device: &str) {
+        self.0.remove(device);
+    }
+
+    /// Whether the guards of an action of a device hold, given the names of
+    /// the watched devices. The inputs of devices that are not watched count
+    /// as released, those of several devices the guard device names as held
+    /// if held on any of them.
+    pub fn allow(
+        &self,
+        device: &str,
+        action: &Action,
+        names: &HashMap<DeviceId, DeviceNames>,
+    ) -> bool {
+        let guarded: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(id, _)| match names.get(*id) {
+                Some(names) => action.guarded_by(device, names),
+                None => action.guarded_by(device, &DeviceNames::unknown(id)),
+            })
+            .map(|(_, values)| values)
+            .collect();
+
+        action.guards_hold(|input| {
+            guarded
+                .iter()
+                .filter_map(|values| values.get(&input))
+                .copied()
+                .find(|&value| value != 0)
+                .unwrap_or_default()
+        })
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Child, Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use nix::{
+    sys::signal::{killpg, Signal},
+    unistd::Pid,
+};
+
+use crate::{
+    config::{ActionType, Hook, HookCommand, HookPolicy},
+    device::DeviceId,
+    input::{Input, InputState},
+    timer::TimerId,
+};
+
+use super::{ActionExecutor, Timer};
+
+/// How often the running commands of hooks are checked for having exited
+const REAP_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a command that timed out has to exit before it is killed
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// The event a hook runs for, passed to its command as environment variables
+#[derive(Debug, Clone)]
+pub(super) struct HookContext {
+    pub device: DeviceId,
+    pub input: Input,
+    pub value: i32,
+    pub timestamp: SystemTime,
+}
+
+/// A command started by a hook that has not been reaped yet
+#[derive(Debug)]
+struct RunningHook {
+    device: DeviceId,
+    /// The hook action, `None` once the actions of the device have been
+    /// replaced or removed
+    action: Option<usize>,
+    child: Child,
+    /// The timer terminating the command once it times out, or killing it
+    /// once it was terminated
+    timeout: Option<TimerId>,
+    /// Whether the command was terminated for timing out
+    timed_out: bool,
+}
+impl RunningHook {
+    fn is(&self, device: &str, action: usize) -> bool {
+        self.device == device && self.action == Some(action)
+    }
+}
+
+/// The commands started by hooks, reaped once they exit
+#[derive(Debug, Default)]
+pub(super) struct Hooks {
+    running: Vec<RunningHook>,
+    /// Runs of queue policy hooks waiting for their running command to exit
+    queued: VecDeque<(usize, HookContext)>,
+    /// Periodic timer reaping exited commands while any are running
+    reaper: Option<TimerId>,
+}
+
+impl ActionExecutor {
+    /// Runs the command of a hook action, unless its policy says otherwise
+    /// while the command is already running
+    pub(super) fn run_hook(&mut self, action: usize, context: HookContext) {
+        let Some(ActionType::Hook { hook, .. }) = self
+            .actions
+            .get(&context.device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| &action.action)
+        else {
+            return;
+        };
+
+        let running = self
+            .hooks
+            .running
+            .iter()
+            .any(|running| running.is(&context.device, action));
+
+        match (hook.policy, running) {
+            (HookPolicy::Single, true) => return,
+            (HookPolicy::Queue, true) => {
+                self.hooks.queued.push_back((action, context));
+                return;
+            }
+            (HookPolicy::Restart, true) => self.kill_hook(&context.device, action),
+            _ => {}
+        }
+
+        self.spawn_hook(action, context);
+    }
+
+    fn spawn_hook(&mut self, action: usize, context: HookContext) {
+        let Some(ActionType::Hook { hook, .. }) = self
+            .actions
+            .get(&context.device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| &action.action)
+        else {
+            return;
+        };
+
+        let Some(mut command) = command(hook, &context) else {
+            return;
+        };
+
+        let child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                println!("Failed to run hook {:?}: {}", hook.cmd, err);
+                return;
+            }
+        };
+
+        let timeout = hook.timeout.map(|timeout| {
+            let timer = self.scheduler.schedule(Duration::from_millis(timeout));
+            self.timers.insert(timer, Timer::HookTimeout);
+
+            timer
+        });
+
+        self.hooks.running.push(RunningHook {
+            device: context.device,
+            action: Some(action),
+            child,
+            timeout,
+            timed_out: false,
+        });
+
+        if self.hooks.reaper.is_none() {
+            let timer = self.scheduler.schedule_periodic(REAP_INTERVAL);
+            self.timers.insert(timer, Timer::HookReaper);
+
+            self.hooks.reaper = Some(timer);
+        }
+    }
+
+    /// Terminates the running commands of a hook action and forgets its
+    /// queued runs
+    pub(super) fn kill_hook(&mut self, device: &str, action: usize) {
+        self.hooks
+            .queued
+            .retain(|(queued, context)| !(context.device == device && *queued == action));
+
+        for running in &self.hooks.running {
+            if running.is(device, action) {
+                terminate(&running.child);
+            }
+        }
+    }
+
+    /// Terminates a command that timed out, killing it if it has not exited
+    /// within the grace period since
+    pub(super) fn time_out_hook(&mut self, timer: TimerId) {
+        let Some(running) = self
+            .hooks
+            .running
+            .iter_mut()
+            .find(|running| running.timeout == Some(timer))
+        else {
+            return;
+        };
+
+        if running.timed_out {
+            running.timeout = None;
+            kill(&running.child);
+            return;
+        }
+
+        terminate(&running.child);
+
+        let timer = self.scheduler.schedule(KILL_GRACE_PERIOD);
+        self.timers.insert(timer, Timer::HookTimeout);
+
+        running.timeout = Some(timer);
+        running.timed_out = true;
+    }
+
+    /// Waits on the commands that have exited, running the next queued run of
+    /// their hooks
+    pub(super) fn reap_hooks(&mut self) {
+        let mut exited = vec![];
+
+        self.hooks
+            .running
+            .retain_mut(|running| match running.child.try_wait() {
+                Ok(None) => true,
+                Ok(Some(_)) | Err(_) => {
+                    exited.push((running.device.clone(), running.action, running.timeout));
+                    false
+                }
+            });
+
+        for (device, action, timeout) in exited {
+            if let Some(timeout) = timeout {
+                self.scheduler.cancel(timeout);
+                self.timers.remove(&timeout);
+            }
+
+            let Some(action) = action else {
+                continue;
+            };
+
+            let next = self
+                .hooks
+                .queued
+                .iter()
+                .position(|(queued, context)| *queued == action && context.device == device);
+
+            if let Some((action, context)) = next.and_then(|idx| self.hooks.queued.remove(idx)) {
+                self.spawn_hook(action, context);
+            }
+        }
+
+        if self.hooks.running.is_empty() {
+            if let Some(reaper) = self.hooks.reaper.take() {
+                self.scheduler.cancel(reaper);
+                self.timers.remove(&reaper);
+            }
+        }
+    }
+
+    /// Stops applying the policies of the hooks of a device, or of every
+    /// device, to their running commands, which are still reaped. Used once
+    /// the actions they were started by no longer exist.
+    pub(super) fn detach_hooks(&mut self, device: Option<&str>) {
+        let detached = |hook_device: &str| match device {
+            Some(device) => device == hook_device,
+            None => true,
+        };
+
+        for running in &mut self.hooks.running {
+            if detached(&running.device) {
+                running.action = None;
+            }
+        }
+
+        self.hooks
+            .queued
+            .retain(|(_, context)| !detached(&context.device));
+    }
+}
+
+fn command(hook: &Hook, context: &HookContext) -> Option<Command> {
+    let mut command = match &hook.cmd {
+        HookCommand::Shell(cmd) => {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(cmd);
+            command
+        }
+        HookCommand::Argv(argv) => {
+            let Some((program, args)) = argv.split_first() else {
+                println!("Hook command is empty");
+                return None;
+            };
+
+            let mut command = Command::new(program);
+            command.args(args);
+            command
+        }
+    };
+
+    let timestamp = context
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    command
+        .envs(&hook.env)
+        .env("COMB_DEVICE", &context.device)
+        .env("COMB_INPUT", context.input.to_string())
+        .env("COMB_VALUE", context.value.to_string())
+        .env(
+            "COMB_TIMESTAMP",
+            format!("{}.{:06}", timestamp.as_secs(), timestamp.subsec_micros()),
+        );
+
+    // Axes have a value rather than a state
+    if context.input.is_toggle() {
+        if let Some(state) = InputState::from_i32(context.value) {
+            command.env("COMB_STATE", state.to_string());
+        }
+    }
+
+    if let Some(cwd) = &hook.cwd {
+        command.current_dir(cwd);
+    }
+
+    command
+        .stdin(Stdio::null())
+        .stdout(log_file(hook.stdout.as_deref()));
+
+    if let Some(stderr) = &hook.stderr {
+        command.stderr(log_file(Some(stderr)));
+    }
+
+    // In its own process group so that the processes it starts are
+    // terminated along with it
+    command.process_group(0);
+
+    Some(command)
+}
+
+/// Opens the file the output of a hook is appended to
+fn log_file(path: Option<&Path>) -> Stdio {
+    let Some(path) = path else {
+        return Stdio::null();
+    };
+
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => file.into(),
+        Err(err) => {
+            println!("Failed to open hook log file {}: {}", path.display(), err);
+            Stdio::null()
+        }
+    }
+}
+
+/// Terminates a command along with the processes it started
+fn terminate(child: &Child) {
+    let _ = killpg(Pid::from_raw(child.id() as i32), Signal::SIGTERM);
+}
+
+/// Kills a command that did not exit when terminated along with the processes
+/// it started
+fn kill(child: &Child) {
+    let _ = killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL);
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use crate::{
+    config::ActionType,
+    device::DeviceId,
+    input::{AbsAxis, Input},
+    timer::TimerId,
+};
+
+use super::{ActionExecutor, OutputId, Timer};
+
+/// How often an absolute axis ramping towards its target is updated
+const RAMP_TICK: Duration = Duration::from_millis(10);
+
+/// An absolute axis driven by keys and buttons
+#[derive(Debug, Default)]
+pub(super) struct KeyAxis {
+    /// The held bindings driving the axis and their deflections, the most
+    /// recently pressed last. The last one decides the deflection of the axis
+    /// so that pressing the opposite key reverses the axis, and releasing it
+    /// returns to the key still held.
+    held: Vec<(DeviceId, usize, f64)>,
+    /// Deflection of the axis between -1 and 1
+    deflection: f64,
+    /// Time in milliseconds the axis takes to move from its centre to full
+    /// deflection
+    ramp: u64,
+    /// Periodic timer moving the axis while it ramps
+    timer: Option<TimerId>,
+}
+impl KeyAxis {
+    fn target(&self) -> f64 {
+        self.held.last().map_or(0.0, |&(_, _, value)| value)
+    }
+}
+
+impl ActionExecutor {
+    /// Presses or releases a key or button bound to an axis
+    pub(super) fn set_key_axis(&mut self, device: &str, action: usize, pressed: bool) {
+        let Some((
+            &ActionType::Bind {
+                to, value, ramp, ..
+            },
+            output,
+        )) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| (&action.action, action.output.as_deref()))
+        else {
+            return;
+        };
+
+        let axis = match to {
+            Input::AbsAxis(axis) => axis,
+            Input::RelAxis(_) => return self.set_mouse_key(device, action, pressed),
+            _ => return,
+        };
+
+        let output = self.outputs.find(output);
+
+        let key_axis = self.key_axes.entry((output, axis)).or_default();
+
+        let held = key_axis
+            .held
+            .iter()
+            .position(|(held_device, held_action, _)| {
+                held_device == device && *held_action == action
+            });
+
+        match (held, pressed) {
+            (None, true) => {
+                key_axis
+                    .held
+                    .push((device.to_string(), action, value.clamp(-1.0, 1.0)))
+            }
+            (Some(idx), false) => {
+                key_axis.held.remove(idx);
+            }
+            // Repeated presses do not move the binding to the top
+            _ => return,
+        }
+
+        key_axis.ramp = ramp;
+
+        self.move_key_axis(output, axis);
+    }
+
+    /// Moves an axis towards the deflection of the binding deciding it,
+    /// straight away or one step of its ramp at a time
+    pub(super) fn move_key_axis(&mut self, output: OutputId, axis: AbsAxis) {
+        let Some(key_axis) = self.key_axes.get_mut(&(output, axis)) else {
+            return;
+        };
+
+        let target = key_axis.target();
+
+        let step = match key_axis.ramp {
+            0 => f64::INFINITY,
+            ramp => RAMP_TICK.as_millis() as f64 / ramp as f64,
+        };
+
+        let deflection = if (target - key_axis.deflection).abs() <= step {
+            target
+        } else {
+            key_axis.deflection + step.copysign(target - key_axis.deflection)
+        };
+
+        if deflection != key_axis.deflection {
+            key_axis.deflection = deflection;
+
+            let value = self.outputs.abs_info(output, axis).map_or(0, |abs_info| {
+                let (minimum, maximum) = (abs_info.minimum() as f64, abs_info.maximum() as f64);
+
+                ((minimum + maximum) / 2.0 + deflection * (maximum - minimum) / 2.0).round() as i32
+            });
+
+            Self::emit(self.outputs.device(output), Input::AbsAxis(axis), value);
+        }
+
+        match (key_axis.timer, deflection == target) {
+            (None, false) => {
+                let timer = self.scheduler.schedule_periodic(RAMP_TICK);
+                self.timers.insert(timer, Timer::KeyAxis(output, axis));
+
+                key_axis.timer = Some(timer);
+            }
+            (Some(timer), true) => {
+                self.scheduler.cancel(timer);
+                self.timers.remove(&timer);
+
+                key_axis.timer = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Releases the bindings of a device driving axes, or of every device
+    pub(super) fn release_key_axes(&mut self, device: Option<&str>) {
+        let axes = self.key_axes.keys().copied().collect::<Vec<_>>();
+
+        for (output, axis) in axes {
+            let Some(key_axis) = self.key_axes.get_mut(&(output, axis)) else {
+                continue;
+            };
+
+            key_axis
+                .held
+                .retain(|(held_device, _, _)| device.is_some_and(|device| device != held_device));
+
+            // Axes are centred straight away rather than left ramping
+            key_axis.ramp = 0;
+
+            self.move_key_axis(output, axis);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::input::Input;
+
+use super::{ActionExecutor, DeviceState, OutputId, Outputs};
+
+impl ActionExecutor {
+    /// Holds down the target of a toggle binding, or releases it if it is
+    /// already held down
+    pub(super) fn toggle_latch(
+        outputs: &mut Outputs,
+        state: &mut DeviceState,
+        output: OutputId,
+        to: Input,
+    ) {
+        if state.latched.remove(&(output, to)) {
+            Self::emit(outputs.device(output), to, 0);
+        } else {
+            state.latched.insert((output, to));
+            Self::emit(outputs.device(output), to, 1);
+        }
+    }
+
+    pub(super) fn release_device_latched(outputs: &mut Outputs, state: &mut DeviceState) {
+        for (output, input) in state.latched.drain() {
+            Self::emit(outputs.device(output), input, 0);
+        }
+    }
+
+    pub(super) fn release_latched(&mut self) {
+        for state in self.states.values_mut() {
+            Self::release_device_latched(&mut self.outputs, state);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    config::{Action, LayerAction, ModifierMode},
+    input::{Chord, Input, InputState},
+};
+
+use super::{ActionExecutor, DeviceState};
+
+impl DeviceState {
+    /// Applies a layer action. Momentary layers follow the state of the bound
+    /// input, the others switch when it is pressed.
+    pub(super) fn switch_layer(&mut self, layer: &LayerAction, input_state: InputState) {
+        match (layer, input_state) {
+            (LayerAction::On(name), InputState::Pressed)
+            | (LayerAction::Momentary(name), InputState::Pressed) => {
+                self.layers.retain(|layer| layer != name);
+                self.layers.push(name.clone());
+            }
+            (LayerAction::Off(name), InputState::Pressed)
+            | (LayerAction::Momentary(name), InputState::Released) => {
+                self.layers.retain(|layer| layer != name);
+            }
+            (LayerAction::Toggle(name), InputState::Pressed) => {
+                if self.layers.contains(name) {
+                    self.layers.retain(|layer| layer != name);
+                } else {
+                    self.layers.push(name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn is_layer_active(&self, layer: Option<&String>) -> bool {
+        match layer {
+            Some(layer) => self.layers.contains(layer),
+            None => true,
+        }
+    }
+
+    /// Whether the modifiers of a chord are held down on the device it
+    /// listens to
+    pub(super) fn is_chord_held(&self, source: &str, chord: &Chord) -> bool {
+        match self.held.get(source) {
+            Some(held) => chord.is_held(held),
+            None => chord.modifiers.is_empty(),
+        }
+    }
+}
+
+impl ActionExecutor {
+    /// Finds the actions an input event should trigger. The most recently
+    /// activated layer with a matching binding wins, falling back to the
+    /// base layer.
+    pub(super) fn matching_actions(
+        actions: &[Action],
+        state: &DeviceState,
+        source: &str,
+        input: Input,
+        allowed: impl Fn(&Action) -> bool + Copy,
+    ) -> Vec<usize> {
+        let layers = state.layers.iter().rev().map(Some);
+
+        layers
+            .chain(std::iter::once(None))
+            .map(|layer| {
+                Self::matching_layer_actions(actions, state, source, input, layer, allowed)
+            })
+            .find(|matching| !matching.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// Finds the matching actions of a single layer. Chords with the most
+    /// held modifiers take precedence over those with fewer, so that
+    /// `key:leftctrl+key:c` shadows a plain `key:c` binding.
+    fn matching_layer_actions(
+        actions: &[Action],
+        state: &DeviceState,
+        source: &str,
+        input: Input,
+        layer: Option<&String>,
+        allowed: impl Fn(&Action) -> bool,
+    ) -> Vec<usize> {
+        let matching = actions.iter().enumerate().filter(|(_, action)| {
+            action.layer.as_ref() == layer
+                && !action.combo
+                && action.bind.sequence.is_empty()
+                && action.is_triggered_by(input)
+                && state.is_chord_held(source, &action.bind)
+                && allowed(action)
+        });
+
+        let Some(most_modifiers) = matching
+            .clone()
+            .map(|(_, action)| action.bind.modifiers.len())
+            .max()
+        else {
+            return vec![];
+        };
+
+        matching
+            .filter(|(_, action)| action.bind.modifiers.len() == most_modifiers)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    pub(super) fn is_consumed_modifier(
+        actions: &[Action],
+        state: &DeviceState,
+        input: Input,
+        listens: impl Fn(&Action) -> bool,
+    ) -> bool {
+        actions.iter().any(|action| {
+            action.modifiers == ModifierMode::Consume
+                && listens(action)
+                && !action.combo
+                && action.bind.modifiers.contains(&input)
+                && state.is_layer_active(action.layer.as_ref())
+        })
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+
+use crate::{
+    config::{ActionType, MacroPolicy, MacroStep},
+    input::Input,
+    timer::TimerId,
+};
+
+use super::{ActionExecutor, OutputId, Timer};
+
+/// A macro part way through being played
+#[derive(Debug)]
+pub(super) struct PlayingMacro {
+    steps: Vec<MacroStep>,
+    next_step: usize,
+    output: OutputId,
+    /// Number of times the macro is played again once it has finished
+    queued: usize,
+    /// Keys and buttons pressed by the macro that have not been released yet
+    pressed: HashSet<Input>,
+    timer: Option<TimerId>,
+}
+
+impl ActionExecutor {
+    pub(super) fn trigger_macro(&mut self, device: &str, action: usize) {
+        let Some((ActionType::Macro { to, policy, .. }, output)) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| (&action.action, action.output.as_deref()))
+        else {
+            return;
+        };
+
+        let steps = to.clone();
+        let policy = *policy;
+        let output = self.outputs.find(output);
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        if let Some(playing) = state.macros.get_mut(&action) {
+            match policy {
+                MacroPolicy::Queue => {
+                    playing.queued += 1;
+                    return;
+                }
+                MacroPolicy::Cancel => {
+                    self.stop_macro(device, action);
+                    return;
+                }
+                MacroPolicy::Restart => self.stop_macro(device, action),
+            }
+        }
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        state.macros.insert(
+            action,
+            PlayingMacro {
+                steps,
+                next_step: 0,
+                output,
+                queued: 0,
+                pressed: HashSet::new(),
+                timer: None,
+            },
+        );
+
+        self.play_macro(device, action);
+    }
+
+    /// Plays the steps of a macro up to its next sleep
+    pub(super) fn play_macro(&mut self, device: &str, action: usize) {
+        let Some(playing) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.macros.get_mut(&action))
+        else {
+            return;
+        };
+
+        playing.timer = None;
+
+        loop {
+            let Some(step) = playing.steps.get(playing.next_step).copied() else {
+                if playing.queued > 0 {
+                    playing.queued -= 1;
+                    playing.next_step = 0;
+
+                    continue;
+                }
+
+                break;
+            };
+
+            playing.next_step += 1;
+
+            match step {
+                MacroStep::Tap(input) => {
+                    Self::emit(self.outputs.device(playing.output), input, 1);
+                    Self::emit(self.outputs.device(playing.output), input, 0);
+                }
+                MacroStep::Set(input, value) => {
+                    Self::emit(self.outputs.device(playing.output), input, value);
+
+                    if input.is_toggle() && value != 0 {
+                        playing.pressed.insert(input);
+                    } else {
+                        playing.pressed.remove(&input);
+                    }
+                }
+                MacroStep::Sleep(duration) => {
+                    let timer = self.scheduler.schedule(duration);
+                    self.timers
+                        .insert(timer, Timer::Macro(device.to_string(), action));
+
+                    playing.timer = Some(timer);
+
+                    return;
+                }
+            }
+        }
+
+        if let Some(state) = self.states.get_mut(device) {
+            state.macros.remove(&action);
+        }
+    }
+
+    /// Stops a playing macro, releasing any keys and buttons it left pressed
+    pub(super) fn stop_macro(&mut self, device: &str, action: usize) {
+        let Some(playing) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.macros.remove(&action))
+        else {
+            return;
+        };
+
+        if let Some(timer) = playing.timer {
+            self.scheduler.cancel(timer);
+            self.timers.remove(&timer);
+        }
+
+        for input in playing.pressed {
+            Self::emit(self.outputs.device(playing.output), input, 0);
+        }
+    }
+
+    pub(super) fn stop_macros(&mut self) {
+        let playing = self
+            .states
+            .iter()
+            .flat_map(|(device, state)| state.macros.keys().map(|&action| (device.clone(), action)))
+            .collect::<Vec<_>>();
+
+        for (device, action) in playing {
+            self.stop_macro(&device, action);
+        }
+    }
+}
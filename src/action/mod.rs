@@ -0,0 +1,893 @@
+mod combo;
+mod gesture;
+mod guard;
+mod hook;
+mod key_axis;
+mod latch;
+mod layer;
+mod macros;
+mod mouse_keys;
+mod outputs;
+mod passthrough;
+mod pointer;
+mod sequence;
+mod tap_hold;
+mod transform;
+mod turbo;
+mod zones;
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use evdev::{uinput::VirtualDevice, AbsInfo, EventType};
+
+use crate::{
+    config::{Action, ActionType, BindMode, Config, LayerAction, WhenCondition},
+    device::{DeviceId, DeviceInput, DeviceNames},
+    input::{AbsAxis, Input, InputState, Key, Layout},
+    timer::{Scheduler, TimerId},
+};
+
+use self::{
+    combo::{ActiveCombo, PendingCombo},
+    gesture::PendingGesture,
+    guard::InputValues,
+    hook::{HookContext, Hooks},
+    key_axis::KeyAxis,
+    macros::PlayingMacro,
+    mouse_keys::MouseKey,
+    outputs::{OutputId, Outputs},
+    passthrough::Passthrough,
+    pointer::PointerMotion,
+    sequence::PendingSequence,
+    tap_hold::PendingTapHold,
+    turbo::Autofire,
+};
+
+#[derive(Debug, Default)]
+struct DeviceState {
+    /// Keys and buttons currently held down, by the device they are held
+    /// down on, which is another device for actions reacting to its events
+    held: HashMap<DeviceId, HashSet<Input>>,
+    /// The actions each held input triggered when it was pressed, so that
+    /// the release reaches the same actions even if the modifiers of their
+    /// chords have been released in the meantime
+    triggered: HashMap<(DeviceId, Input), Vec<usize>>,
+    /// Presses held back until the combos they can be part of are either
+    /// completed or given up on
+    combo: Option<PendingCombo>,
+    /// The completed combos whose inputs are still held down
+    active_combos: Vec<ActiveCombo>,
+    /// The sequence being typed on the device
+    sequence: Option<PendingSequence>,
+    /// The actions of completed sequences whose trigger is still held down,
+    /// by trigger
+    sequence_triggers: HashMap<Input, usize>,
+    /// The earlier steps of completed sequences that are still held down,
+    /// like a leader key, whose releases only belonged to the sequence
+    sequence_steps: HashSet<Input>,
+    /// The tap-hold action waiting to be resolved as either a tap or a hold
+    tap_hold: Option<PendingTapHold>,
+    /// The hold targets pressed by tap-hold actions resolved as a hold, and
+    /// the output they were pressed through, by the input of the action
+    tap_hold_held: HashMap<Input, (OutputId, Input)>,
+    /// Inputs held back until the pending tap-hold action has been resolved
+    held_back: Vec<DeviceInput>,
+    /// The multi-tap actions whose last tap is still held down, released
+    /// along with it, by the device and input of the tap
+    multi_tapped: HashMap<(DeviceId, Input), Vec<usize>>,
+    /// The multi-tap or long-press sequence currently being performed
+    gesture: Option<PendingGesture>,
+    /// The macros being played, by action
+    macros: HashMap<usize, PlayingMacro>,
+    /// The active layers of the device, the most recently activated last
+    layers: Vec<String>,
+    /// Targets of toggle bindings currently held down, and the outputs they
+    /// are held down on
+    latched: HashSet<(OutputId, Input)>,
+    /// The turbo bindings whose source is held down, by action
+    turbo: HashMap<usize, Autofire>,
+    /// The deflection of the sticks of pointer actions, by action
+    pointers: HashMap<usize, PointerMotion>,
+    /// The zone each zones action is in and the target it holds down on its
+    /// output, by action
+    zones: HashMap<usize, (usize, OutputId, Input)>,
+    /// Held keys and buttons moving relative axes, by action
+    mouse_keys: HashMap<usize, MouseKey>,
+    /// Relative motion of transformed bindings not yet emitted because it is
+    /// less than one unit, by action
+    remainders: HashMap<usize, f64>,
+    /// The last value of each input, by the device it came from, for edge
+    /// conditions
+    values: HashMap<(DeviceId, Input), i32>,
+}
+
+/// What an expired timer was started for
+#[derive(Debug)]
+enum Timer {
+    Combo(DeviceId),
+    Sequence(DeviceId),
+    TapHold(DeviceId),
+    GestureHold(DeviceId, usize),
+    GestureWindow(DeviceId),
+    Macro(DeviceId, usize),
+    /// Periodic timer of a turbo binding
+    Turbo(DeviceId, usize),
+    TurboRelease(DeviceId, usize),
+    /// Periodic timer of a pointer action
+    Pointer(DeviceId, usize),
+    /// Periodic timer of an absolute axis ramping towards the deflection of
+    /// the keys driving it, by output
+    KeyAxis(OutputId, AbsAxis),
+    /// Periodic timer of a key or button moving a relative axis
+    MouseKey(DeviceId, usize),
+    /// Periodic timer reaping the commands of hooks that have exited
+    HookReaper,
+    /// Timeout of the command of a hook, the running command being found by
+    /// the id of the timer
+    HookTimeout,
+}
+impl Timer {
+    fn device(&self) -> Option<&str> {
+        match self {
+            Self::Combo(device)
+            | Self::Sequence(device)
+            | Self::TapHold(device)
+            | Self::GestureHold(device, _)
+            | Self::GestureWindow(device)
+            | Self::Macro(device, _)
+            | Self::Turbo(device, _)
+            | Self::TurboRelease(device, _)
+            | Self::Pointer(device, _)
+            | Self::MouseKey(device, _) => Some(device),
+            Self::KeyAxis(..) | Self::HookReaper | Self::HookTimeout => None,
+        }
+    }
+}
+
+pub struct ActionExecutor {
+    actions: HashMap<DeviceId, Vec<Action>>,
+    states: HashMap<DeviceId, DeviceState>,
+    scheduler: Scheduler,
+    timers: HashMap<TimerId, Timer>,
+    outputs: Outputs,
+    /// Absolute axes of the virtual devices driven by keys and buttons
+    key_axes: HashMap<(OutputId, AbsAxis), KeyAxis>,
+    layout: Layout,
+    /// The ranges of the absolute axes of each watched device
+    abs_info: HashMap<DeviceId, HashMap<AbsAxis, AbsInfo>>,
+    /// The virtual devices the unused events of grabbed devices are passed
+    /// on through
+    passthrough: HashMap<DeviceId, Passthrough>,
+    /// The devices running a copy of the actions of the configured device
+    /// they match, because another device it matches was watched first
+    copies: HashSet<DeviceId>,
+    /// The accessor of each configured device, which `from` and
+    /// `guard_device` can name it by
+    accessors: HashMap<DeviceId, String>,
+    /// The names each watched device goes by in the config
+    names: HashMap<DeviceId, DeviceNames>,
+    hooks: Hooks,
+    input_values: InputValues,
+}
+impl ActionExecutor {
+    pub fn from_config(config: Config, scheduler: Scheduler) -> Self {
+        let layout = config.layout;
+
+        let accessors = accessors(&config);
+        let actions = config
+            .devices
+            .into_iter()
+            .map(|dev| (dev.id(), dev.into_actions()))
+            .collect();
+
+        // The ranges of the source axes are only known once their devices
+        // have been added
+        let outputs = Outputs::new(config.outputs, &actions, &HashMap::new(), layout);
+
+        Self {
+            actions,
+            states: HashMap::new(),
+            scheduler,
+            timers: HashMap::new(),
+            outputs,
+            key_axes: HashMap::new(),
+            layout,
+            abs_info: HashMap::new(),
+            passthrough: HashMap::new(),
+            copies: HashSet::new(),
+            accessors,
+            names: HashMap::new(),
+            hooks: Hooks::default(),
+            input_values: InputValues::default(),
+        }
+    }
+
+    /// Recreates the virtual devices if the actions can emit inputs they do
+    /// not support
+    fn update_virtual_device(&mut self) {
+        self.outputs
+            .update(&self.actions, &self.abs_info, self.layout);
+    }
+
+    pub fn update_config(&mut self, config: Config) {
+        self.layout = config.layout;
+        self.accessors = accessors(&config);
+        self.actions = config
+            .devices
+            .into_iter()
+            .map(|dev| (dev.id(), dev.into_actions()))
+            .collect();
+        self.outputs.set_config(config.outputs, &self.actions);
+
+        self.stop_macros();
+        self.stop_turbos();
+        self.release_latched();
+        self.release_tap_holds();
+        self.release_zones();
+        self.release_key_axes(None);
+
+        // Recorded action indices refer to the previous config, the copies are
+        // made again as the devices are watched again
+        self.states.clear();
+        self.copies.clear();
+        self.detach_hooks(None);
+
+        // The commands of hooks keep being supervised
+        let scheduler = &self.scheduler;
+        self.timers.retain(|&id, timer| {
+            if matches!(timer, Timer::HookReaper | Timer::HookTimeout) {
+                return true;
+            }
+
+            scheduler.cancel(id);
+            false
+        });
+
+        self.update_virtual_device();
+    }
+
+    pub fn handle_input(&mut self, input: DeviceInput) {
+        self.input_values.update(&input);
+
+        // The actions of other devices can react to the device too
+        for device in self.routes(input.device()) {
+            self.handle_device_input(input.routed_to(device));
+        }
+
+        self.handle_device_input(input);
+    }
+
+    /// The names a device goes by in the config, only its id if it is not
+    /// watched
+    fn device_names(&self, device: &str) -> DeviceNames {
+        self.names
+            .get(device)
+            .cloned()
+            .unwrap_or_else(|| DeviceNames::unknown(device))
+    }
+
+    /// The other devices whose actions react to the events of a device
+    fn routes(&self, source: &str) -> Vec<DeviceId> {
+        let names = self.device_names(source);
+
+        self.actions
+            .iter()
+            .filter(|(device, actions)| {
+                *device != source
+                    && actions
+                        .iter()
+                        .any(|action| action.from.is_some() && action.listens_to(device, &names))
+            })
+            .map(|(device, _)| device.clone())
+            .collect()
+    }
+
+    /// Releases the keys and buttons a device that is no longer watched left
+    /// held down for the actions of other devices reacting to it
+    fn release_routed(&mut self, source: &str) {
+        let held = self.input_values.held(source);
+
+        for device in self.routes(source) {
+            for &input in &held {
+                if let Some(input) = DeviceInput::released(device.clone(), source, input) {
+                    self.handle_device_input(input);
+                }
+            }
+        }
+    }
+
+    /// Handles an input with the actions of a single device
+    fn handle_device_input(&mut self, input: DeviceInput) {
+        if self.hold_combo(&input) {
+            return;
+        }
+
+        self.process_input(input);
+    }
+
+    /// Handles an input that is not held back for a combo
+    fn process_input(&mut self, input: DeviceInput) {
+        if self.hold_sequence(&input) {
+            return;
+        }
+
+        self.run_input(input);
+    }
+
+    /// Handles an input that is not held back for a combo or a sequence
+    fn run_input(&mut self, input: DeviceInput) {
+        if self.hold_back(&input) {
+            return;
+        }
+
+        let Some(triggered) = self.track_input(&input) else {
+            return;
+        };
+
+        if self.release_multi_tap(&input) {
+            return;
+        }
+
+        // Events of grabbed devices would otherwise be lost
+        if triggered.is_empty()
+            && input.source() == input.device()
+            && !self.reacts_elsewhere(&input)
+        {
+            if let Some(passthrough) = self.passthrough.get_mut(input.device()) {
+                let event = input.input_event();
+
+                passthrough.push(Self::raw_event(event.input(), event.state()));
+            }
+        }
+
+        if self.defer_gesture(&input, &triggered) {
+            return;
+        }
+
+        self.dispatch(&input, &triggered);
+        self.release_held_back(input.device());
+    }
+
+    /// Whether the actions of other devices depend on the input
+    fn reacts_elsewhere(&self, input: &DeviceInput) -> bool {
+        let device = self.device_names(input.source());
+        let input = input.input_event().input();
+
+        self.actions.iter().any(|(section, actions)| {
+            section != device.id()
+                && actions
+                    .iter()
+                    .any(|action| action.depends_on(section, &device, input))
+        })
+    }
+
+    /// Updates the held inputs of the device and finds the actions the input
+    /// triggers
+    fn track_input(&mut self, input: &DeviceInput) -> Option<Vec<usize>> {
+        let actions = self.actions.get(input.device())?;
+
+        let names = self.device_names(input.source());
+        let state = self.states.entry(input.device().to_string()).or_default();
+
+        let device = input.device();
+        let source = input.source();
+        let listens = |action: &Action| action.listens_to(device, &names);
+        let allowed = |action: &Action| {
+            listens(action) && self.input_values.allow(device, action, &self.names)
+        };
+
+        let input_state = input.input_event().state();
+        let input = input.input_event().input();
+
+        let key = (source.to_string(), input);
+
+        let triggered = match InputState::from_i32(input_state) {
+            _ if !input.is_toggle() => {
+                Self::matching_actions(actions, state, source, input, allowed)
+            }
+            Some(InputState::Pressed) => {
+                let triggered = Self::matching_actions(actions, state, source, input, allowed);
+
+                state
+                    .held
+                    .entry(source.to_string())
+                    .or_default()
+                    .insert(input);
+                state.triggered.insert(key, triggered.clone());
+
+                triggered
+            }
+            Some(InputState::Repeated) => state.triggered.get(&key).cloned().unwrap_or_default(),
+            Some(InputState::Released) | None => {
+                if let Some(held) = state.held.get_mut(source) {
+                    held.remove(&input);
+
+                    if held.is_empty() {
+                        state.held.remove(source);
+                    }
+                }
+
+                state.triggered.remove(&key).unwrap_or_default()
+            }
+        };
+
+        if Self::is_consumed_modifier(actions, state, input, listens) {
+            return None;
+        }
+
+        Some(triggered)
+    }
+
+    fn dispatch(&mut self, input: &DeviceInput, triggered: &[usize]) {
+        let device = input.device();
+        let event = input.input_event();
+        let timestamp = input.timestamp();
+
+        let Some(actions) = self.actions.get(device) else {
+            return;
+        };
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        let from = input.source();
+        let input_state = event.state();
+        let input = event.input();
+
+        let previous = state.values.insert((from.to_string(), input), input_state);
+
+        let mut macros = vec![];
+        let mut turbos = vec![];
+        let mut pointers = vec![];
+        let mut zones = vec![];
+        let mut key_axes = vec![];
+        let mut hooks = vec![];
+
+        for (idx, action) in triggered.iter().map(|&idx| (idx, &actions[idx])) {
+            // Multi-tap and long-press actions are run once the gesture has
+            // been recognised
+            if action.action.gesture().is_some() {
+                continue;
+            }
+
+            let output = self.outputs.find(action.output.as_deref());
+
+            match action.action {
+                ActionType::Hook { when, ref hook } => {
+                    if when.test_edge(previous, input_state) {
+                        hooks.push((idx, true));
+                    } else if hook.kill_on_release
+                        && input.is_toggle()
+                        && InputState::from_i32(input_state) == Some(InputState::Released)
+                    {
+                        hooks.push((idx, false));
+                    }
+                }
+                ActionType::Print { when, ref print } => {
+                    if when.test_edge(previous, input_state) {
+                        Self::execute_print(self.outputs.device(output), self.layout, print);
+                    }
+                }
+                ActionType::Bind {
+                    when,
+                    to,
+                    mode: BindMode::Toggle,
+                    ..
+                } if to.is_toggle() => {
+                    let pressed = match when {
+                        Some(when) => when.test(input_state),
+                        None => InputState::from_i32(input_state) == Some(InputState::Pressed),
+                    };
+
+                    if pressed {
+                        Self::toggle_latch(&mut self.outputs, state, output, to);
+                    }
+                }
+                ActionType::Bind {
+                    when,
+                    to,
+                    mode: BindMode::Turbo,
+                    ..
+                } if to.is_toggle() => {
+                    let active = match when {
+                        Some(when) => when.test(input_state),
+                        None => input_state != 0,
+                    };
+
+                    turbos.push((idx, active));
+                }
+                ActionType::Bind {
+                    when,
+                    to,
+                    ref transform,
+                    ..
+                } if !input.is_toggle() && !to.is_toggle() => {
+                    // Axes the source device reports no range for get the
+                    // default one, like in `axis_position`
+                    let source = match input {
+                        Input::AbsAxis(axis) => Some(
+                            self.abs_info
+                                .get(from)
+                                .and_then(|abs_info| abs_info.get(&axis))
+                                .copied()
+                                .unwrap_or_else(|| {
+                                    AbsInfo::new(0, i16::MIN as i32, i16::MAX as i32, 0, 0, 0)
+                                }),
+                        ),
+                        _ => None,
+                    };
+                    let source = source.as_ref();
+                    let target = match to {
+                        Input::AbsAxis(axis) => self.outputs.abs_info(output, axis),
+                        _ => None,
+                    };
+
+                    if transform.is_identity() && !Self::rescales(source, target) {
+                        Self::execute_bind(
+                            self.outputs.device(output),
+                            input,
+                            to,
+                            when,
+                            input_state,
+                        );
+                        continue;
+                    }
+
+                    let centred = match input {
+                        Input::AbsAxis(axis) => transform.is_centred(axis),
+                        _ => false,
+                    };
+
+                    let value = match when {
+                        Some(when) if !when.test(input_state) => 0,
+                        _ => Self::transform_axis(
+                            transform,
+                            centred,
+                            source,
+                            target,
+                            state.remainders.entry(idx).or_default(),
+                            input_state,
+                        ),
+                    };
+
+                    Self::emit(self.outputs.device(output), to, value);
+                }
+                ActionType::Bind { when, to, .. } if input.is_toggle() && !to.is_toggle() => {
+                    let pressed = match when {
+                        Some(when) => when.test(input_state),
+                        None => input_state != 0,
+                    };
+
+                    key_axes.push((idx, pressed));
+                }
+                ActionType::Bind { when, to, .. } => {
+                    Self::execute_bind(self.outputs.device(output), input, to, when, input_state)
+                }
+                ActionType::Macro { when, .. } => {
+                    if when.test_edge(previous, input_state) {
+                        macros.push(idx);
+                    }
+                }
+                ActionType::TapHold {
+                    tap,
+                    hold,
+                    tapping_term,
+                    flavor,
+                } => match InputState::from_i32(input_state) {
+                    Some(InputState::Pressed) => {
+                        let timer = self.scheduler.schedule(Duration::from_millis(tapping_term));
+                        self.timers
+                            .insert(timer, Timer::TapHold(device.to_string()));
+
+                        state.tap_hold = Some(PendingTapHold {
+                            input,
+                            output,
+                            tap,
+                            hold,
+                            flavor,
+                            timer,
+                        });
+                    }
+                    Some(InputState::Released) => match state.tap_hold.take() {
+                        // Released before being resolved as a hold
+                        Some(pending) if pending.input == input => {
+                            self.scheduler.cancel(pending.timer);
+                            self.timers.remove(&pending.timer);
+
+                            Self::emit(self.outputs.device(output), tap, 1);
+                            Self::emit(self.outputs.device(output), tap, 0);
+                        }
+                        pending => {
+                            state.tap_hold = pending;
+
+                            // Only a hold that was pressed for the input is
+                            // released
+                            if let Some((output, hold)) = state.tap_hold_held.remove(&input) {
+                                Self::emit(self.outputs.device(output), hold, 0);
+                            }
+                        }
+                    },
+                    _ => {}
+                },
+                ActionType::Pointer { .. } => pointers.push(idx),
+                ActionType::Zones { .. } => zones.push(idx),
+                ActionType::Layer { ref layer, when } => match layer {
+                    LayerAction::Momentary(_) => {
+                        if let Some(input_state) = InputState::from_i32(input_state) {
+                            state.switch_layer(layer, input_state);
+                        }
+                    }
+                    _ if when.test_edge(previous, input_state) => {
+                        state.switch_layer(layer, InputState::Pressed)
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        for action in macros {
+            self.trigger_macro(device, action);
+        }
+
+        for (action, active) in turbos {
+            self.set_turbo(device, action, active);
+        }
+
+        for action in pointers {
+            self.move_pointer(device, action, input, input_state);
+        }
+
+        for action in zones {
+            self.update_zone(device, action, input, input_state);
+        }
+
+        for (action, pressed) in key_axes {
+            self.set_key_axis(device, action, pressed);
+        }
+
+        for (action, run) in hooks {
+            if run {
+                let context = HookContext {
+                    device: device.to_string(),
+                    input,
+                    value: input_state,
+                    timestamp,
+                };
+
+                self.run_hook(action, context);
+            } else {
+                self.kill_hook(device, action);
+            }
+        }
+    }
+
+    pub fn add_device(
+        &mut self,
+        device: DeviceId,
+        section: DeviceId,
+        abs_info: HashMap<AbsAxis, AbsInfo>,
+        passthrough: Option<VirtualDevice>,
+    ) {
+        if device != section {
+            if let Some(actions) = self.actions.get(&section).cloned() {
+                self.actions.insert(device.clone(), actions);
+                self.copies.insert(device.clone());
+            }
+        }
+
+        let accessor = self.accessors.get(&section).cloned();
+        let names = DeviceNames::new(
+            device.clone(),
+            section.clone(),
+            accessor.unwrap_or_else(|| section.clone()),
+        );
+        self.names.insert(device.clone(), names);
+
+        match passthrough {
+            Some(passthrough) => self
+                .passthrough
+                .insert(device.clone(), Passthrough::new(passthrough)),
+            None => self.passthrough.remove(&device),
+        };
+        self.abs_info.insert(device, abs_info);
+
+        self.update_virtual_device();
+    }
+
+    /// Forgets the state of a device that is no longer watched, releasing
+    /// anything it left held down on the virtual device
+    pub fn remove_device(&mut self, device: &str) {
+        self.release_routed(device);
+
+        self.abs_info.remove(device);
+        self.passthrough.remove(device);
+        self.input_values.remove_device(device);
+        self.detach_hooks(Some(device));
+        self.release_key_axes(Some(device));
+
+        let (macros, turbos) = self
+            .states
+            .get(device)
+            .map(|state| {
+                (
+                    state.macros.keys().copied().collect::<Vec<_>>(),
+                    state.turbo.keys().copied().collect::<Vec<_>>(),
+                )
+            })
+            .unwrap_or_default();
+
+        for action in macros {
+            self.stop_macro(device, action);
+        }
+
+        for action in turbos {
+            self.stop_turbo(device, action);
+        }
+
+        let scheduler = &self.scheduler;
+        self.timers.retain(|&id, timer| {
+            if timer.device() != Some(device) {
+                return true;
+            }
+
+            scheduler.cancel(id);
+            false
+        });
+
+        if self.copies.remove(device) {
+            self.actions.remove(device);
+        }
+        self.names.remove(device);
+
+        let Some(mut state) = self.states.remove(device) else {
+            return;
+        };
+
+        Self::release_device_latched(&mut self.outputs, &mut state);
+        Self::release_device_tap_holds(&mut self.outputs, &mut state);
+        Self::release_device_zones(&mut self.outputs, &mut state);
+    }
+
+    pub fn handle_timer(&mut self, id: TimerId) {
+        let Some(timer) = self.timers.remove(&id) else {
+            return;
+        };
+
+        // Inputs replayed by the timer are passed on outside of the frames of
+        // their device
+        let device = timer.device().map(str::to_string);
+
+        match timer {
+            Timer::Combo(device) => self.flush_combo(&device),
+            Timer::Sequence(device) => self.flush_sequence(&device),
+            Timer::TapHold(device) => self.resolve_tap_hold(&device, true),
+            Timer::GestureHold(device, action) => self.run_long_press(&device, action),
+            Timer::GestureWindow(device) => self.resolve_gesture(&device),
+            Timer::Macro(device, action) => self.play_macro(&device, action),
+            Timer::Turbo(device, action) => {
+                // Periodic timers keep expiring until they are cancelled
+                self.timers.insert(id, Timer::Turbo(device.clone(), action));
+
+                self.fire_turbo(&device, action);
+            }
+            Timer::TurboRelease(device, action) => self.release_turbo(&device, action),
+            Timer::Pointer(device, action) => {
+                self.timers
+                    .insert(id, Timer::Pointer(device.clone(), action));
+
+                self.tick_pointer(&device, action);
+            }
+            Timer::MouseKey(device, action) => {
+                self.timers
+                    .insert(id, Timer::MouseKey(device.clone(), action));
+
+                self.tick_mouse_key(&device, action);
+            }
+            Timer::KeyAxis(output, axis) => {
+                self.timers.insert(id, Timer::KeyAxis(output, axis));
+
+                self.move_key_axis(output, axis);
+            }
+            Timer::HookReaper => {
+                self.timers.insert(id, Timer::HookReaper);
+
+                self.reap_hooks();
+            }
+            Timer::HookTimeout => self.time_out_hook(id),
+        }
+
+        if let Some(device) = device {
+            self.flush_passthrough(&device);
+        }
+    }
+
+    /// Position of an absolute axis of a device between 0 at its minimum and
+    /// 1 at its maximum
+    fn axis_position(&self, device: &str, axis: AbsAxis, value: i32) -> f64 {
+        let (minimum, maximum) = self
+            .abs_info
+            .get(device)
+            .and_then(|abs_info| abs_info.get(&axis))
+            .map(|info| (info.minimum(), info.maximum()))
+            .filter(|(minimum, maximum)| minimum < maximum)
+            .unwrap_or((i16::MIN as i32, i16::MAX as i32));
+
+        let position = (value as f64 - minimum as f64) / (maximum as f64 - minimum as f64);
+
+        position.clamp(0.0, 1.0)
+    }
+
+    fn execute_print(virtual_device: &mut VirtualDevice, layout: Layout, print: &str) {
+        let shift = Input::Key(Key::Leftshift);
+
+        for c in print.chars() {
+            let Some((key, shifted)) = layout.key(c) else {
+                println!("Unable to type {:?} using the {:?} layout", c, layout);
+                continue;
+            };
+
+            if shifted {
+                Self::emit(virtual_device, shift, 1);
+            }
+
+            Self::emit(virtual_device, Input::Key(key), 1);
+            Self::emit(virtual_device, Input::Key(key), 0);
+
+            if shifted {
+                Self::emit(virtual_device, shift, 0);
+            }
+        }
+    }
+
+    fn execute_bind(
+        virtual_device: &mut VirtualDevice,
+        input: Input,
+        to: Input,
+        when: Option<WhenCondition>,
+        state: i32,
+    ) {
+        let state = if when.map(|when| when.test(state)).unwrap_or(true) {
+            if !input.is_toggle() && to.is_toggle() {
+                InputState::Pressed.as_i32()
+            } else {
+                state
+            }
+        } else {
+            0
+        };
+
+        Self::emit(virtual_device, to, state);
+    }
+
+    fn emit(virtual_device: &mut VirtualDevice, to: Input, value: i32) {
+        let _ = virtual_device.emit(&[Self::raw_event(to, value)]);
+    }
+
+    fn raw_event(to: Input, value: i32) -> evdev::InputEvent {
+        let (type_, key): (_, u16) = match to {
+            Input::Key(key) => (EventType::KEY, Into::<evdev::Key>::into(key).0),
+            Input::Btn(btn) => (EventType::KEY, Into::<evdev::Key>::into(btn).0),
+            Input::RelAxis(axis) => (
+                EventType::RELATIVE,
+                Into::<evdev::RelativeAxisType>::into(axis).0,
+            ),
+            Input::AbsAxis(axis) => (
+                EventType::ABSOLUTE,
+                Into::<evdev::AbsoluteAxisType>::into(axis).0,
+            ),
+        };
+
+        evdev::InputEvent::new(type_, key, value)
+    }
+}
+
+/// The accessor of each configured device, by the id of the device
+fn accessors(config: &Config) -> HashMap<DeviceId, String> {
+    config
+        .devices
+        .iter()
+        .map(|dev| (dev.id(), dev.accessor.to_string()))
+        .collect()
+}
@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use crate::{config::ActionType, timer::TimerId};
+
+use super::{ActionExecutor, Timer};
+
+/// A held key or button moving a relative axis
+#[derive(Debug)]
+pub(super) struct MouseKey {
+    pressed_at: Instant,
+    /// Motion not yet emitted because it is less than one unit
+    remainder: f64,
+    timer: TimerId,
+}
+
+impl ActionExecutor {
+    /// Starts or stops moving the relative axis bound to a key or button
+    pub(super) fn set_mouse_key(&mut self, device: &str, action: usize, pressed: bool) {
+        let Some((
+            &ActionType::Bind {
+                to, value, motion, ..
+            },
+            output,
+        )) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| (&action.action, action.output.as_deref()))
+        else {
+            return;
+        };
+
+        let output = self.outputs.find(output);
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        match (state.mouse_keys.contains_key(&action), pressed) {
+            (false, true) => {
+                if !motion.tick_rate.is_finite() || motion.tick_rate <= 0.0 {
+                    println!(
+                        "Invalid mouse key tick rate {}, expected ticks per second",
+                        motion.tick_rate
+                    );
+                    return;
+                }
+
+                let timer = self
+                    .scheduler
+                    .schedule_periodic(Duration::from_secs_f64(1.0 / motion.tick_rate));
+                self.timers
+                    .insert(timer, Timer::MouseKey(device.to_string(), action));
+
+                state.mouse_keys.insert(
+                    action,
+                    MouseKey {
+                        pressed_at: Instant::now(),
+                        remainder: 0.0,
+                        timer,
+                    },
+                );
+
+                // A short press moves by a single unit
+                Self::emit(self.outputs.device(output), to, value.signum() as i32);
+            }
+            (true, false) => {
+                if let Some(mouse_key) = state.mouse_keys.remove(&action) {
+                    self.scheduler.cancel(mouse_key.timer);
+                    self.timers.remove(&mouse_key.timer);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Emits the motion of a held key or button for one tick, its speed
+    /// following the acceleration curve until the ramp is over
+    pub(super) fn tick_mouse_key(&mut self, device: &str, action: usize) {
+        let Some((
+            &ActionType::Bind {
+                to,
+                value,
+                ramp,
+                motion,
+                ..
+            },
+            output,
+        )) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| (&action.action, action.output.as_deref()))
+        else {
+            return;
+        };
+
+        let output = self.outputs.find(output);
+
+        let Some(mouse_key) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.mouse_keys.get_mut(&action))
+        else {
+            return;
+        };
+
+        let progress = match ramp {
+            0 => 1.0,
+            ramp => (mouse_key.pressed_at.elapsed().as_millis() as f64 / ramp as f64).min(1.0),
+        };
+
+        let speed = value * motion.sensitivity * progress.powf(motion.acceleration);
+
+        let distance = speed / motion.tick_rate + mouse_key.remainder;
+        let units = distance.trunc();
+
+        mouse_key.remainder = distance - units;
+
+        if units != 0.0 {
+            Self::emit(self.outputs.device(output), to, units as i32);
+        }
+    }
+}
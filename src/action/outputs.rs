@@ -0,0 +1,294 @@
+use std::collections::{BTreeSet, HashMap};
+
+use evdev::{
+    uinput::{VirtualDevice, VirtualDeviceBuilder},
+    AbsInfo, AttributeSet, UinputAbsSetup,
+};
+
+use crate::{
+    config::{Action, ActionType, AxisInfo, Output},
+    device::DeviceId,
+    input::{AbsAxis, Input, Layout},
+};
+
+/// Index of a virtual device actions emit through, the default device being 0
+pub(super) type OutputId = usize;
+
+const DEFAULT_OUTPUT: OutputId = 0;
+
+/// A virtual device and the inputs it supports
+struct VirtualOutput {
+    /// The declared output, `None` for the default device
+    config: Option<Output>,
+    device: VirtualDevice,
+    keys: AttributeSet<evdev::Key>,
+    rel_axis: AttributeSet<evdev::RelativeAxisType>,
+    abs_axis: HashMap<AbsAxis, AbsInfo>,
+}
+
+/// The virtual devices the actions emit through: the default device followed
+/// by the declared outputs
+pub(super) struct Outputs {
+    config: Vec<Output>,
+    outputs: Vec<VirtualOutput>,
+}
+impl Outputs {
+    pub fn new(
+        config: Vec<Output>,
+        actions: &HashMap<DeviceId, Vec<Action>>,
+        abs_info: &HashMap<DeviceId, HashMap<AbsAxis, AbsInfo>>,
+        layout: Layout,
+    ) -> Self {
+        let mut outputs = Self {
+            config,
+            outputs: vec![],
+        };
+
+        outputs.warn_unknown(actions);
+        outputs.update(actions, abs_info, layout);
+
+        outputs
+    }
+
+    pub fn set_config(&mut self, config: Vec<Output>, actions: &HashMap<DeviceId, Vec<Action>>) {
+        self.config = config;
+
+        self.warn_unknown(actions);
+    }
+
+    fn declared(&self, name: &str) -> bool {
+        self.config.iter().any(|output| output.name == name)
+    }
+
+    /// Warns once about each output named by the actions that was not
+    /// declared
+    fn warn_unknown(&self, actions: &HashMap<DeviceId, Vec<Action>>) {
+        let unknown: BTreeSet<&str> = actions
+            .values()
+            .flatten()
+            .filter_map(|action| action.output.as_deref())
+            .filter(|name| !self.declared(name))
+            .collect();
+
+        for name in unknown {
+            println!(
+                "Unknown output {:?}, using the default virtual device",
+                name
+            );
+        }
+    }
+
+    /// The output of an action, falling back to the default device if it
+    /// names an output that was not declared
+    pub fn find(&self, name: Option<&str>) -> OutputId {
+        name.and_then(|name| {
+            self.outputs.iter().position(|output| {
+                output
+                    .config
+                    .as_ref()
+                    .is_some_and(|config| config.name == name)
+            })
+        })
+        .unwrap_or(DEFAULT_OUTPUT)
+    }
+
+    pub fn device(&mut self, output: OutputId) -> &mut VirtualDevice {
+        let output = if output < self.outputs.len() {
+            output
+        } else {
+            DEFAULT_OUTPUT
+        };
+
+        &mut self.outputs[output].device
+    }
+
+    /// The range of an absolute axis of an output
+    pub fn abs_info(&self, output: OutputId, axis: AbsAxis) -> Option<&AbsInfo> {
+        self.outputs
+            .get(output)
+            .and_then(|output| output.abs_axis.get(&axis))
+    }
+
+    /// Recreates the virtual devices whose declaration changed or whose
+    /// actions can emit inputs they do not support
+    pub fn update(
+        &mut self,
+        actions: &HashMap<DeviceId, Vec<Action>>,
+        abs_info: &HashMap<DeviceId, HashMap<AbsAxis, AbsInfo>>,
+        layout: Layout,
+    ) {
+        let mut previous = std::mem::take(&mut self.outputs);
+
+        let configs = std::iter::once(None).chain(self.config.iter().map(Some));
+
+        for config in configs {
+            let name = config.map(|config| config.name.as_str());
+
+            // Actions naming an unknown output emit through the default device
+            let emits_through = |action: &&Action| {
+                action.output.as_deref().filter(|name| self.declared(name)) == name
+            };
+
+            let actions = actions.iter().flat_map(|(device, actions)| {
+                actions
+                    .iter()
+                    .filter(emits_through)
+                    .map(move |action| (device, action))
+            });
+
+            let mut keys = keys_from_actions(actions.clone().map(|(_, action)| action), layout);
+            let rel_axis = rel_axis_from_actions(actions.clone().map(|(_, action)| action), layout);
+            let mut abs_axis = abs_axis_from_actions(actions, abs_info, layout);
+
+            // Profiles declare every input of their controller, with its
+            // exact range, whether bound or not
+            if let Some(profile) = config.and_then(|config| config.profile) {
+                for &btn in profile.buttons() {
+                    keys.insert(btn.into());
+                }
+
+                abs_axis.extend(profile.axes());
+            }
+
+            let current = previous.iter().position(|output| {
+                output.config.as_ref() == config
+                    && keys.iter().all(|key| output.keys.contains(key))
+                    && rel_axis.iter().all(|axis| output.rel_axis.contains(axis))
+                    && abs_axis.iter().all(|(axis, abs_info)| {
+                        output
+                            .abs_axis
+                            .get(axis)
+                            .is_some_and(|current| abs_range(current) == abs_range(abs_info))
+                    })
+            });
+
+            let output = match current {
+                Some(idx) => previous.swap_remove(idx),
+                None => VirtualOutput {
+                    config: config.cloned(),
+                    device: new_virtual_device(config, &keys, &rel_axis, &abs_axis),
+                    keys,
+                    rel_axis,
+                    abs_axis,
+                },
+            };
+
+            self.outputs.push(output);
+        }
+    }
+}
+
+fn keys_from_actions<'a>(
+    actions: impl Iterator<Item = &'a Action>,
+    layout: Layout,
+) -> AttributeSet<evdev::Key> {
+    let mut keys = AttributeSet::<evdev::Key>::new();
+
+    let binds = actions.flat_map(|action| action.action.outputs(layout));
+
+    for bind in binds {
+        let key: evdev::Key = match bind {
+            Input::Key(key) => key.into(),
+            Input::Btn(btn) => btn.into(),
+            _ => continue,
+        };
+
+        keys.insert(key);
+    }
+
+    keys
+}
+
+fn rel_axis_from_actions<'a>(
+    actions: impl Iterator<Item = &'a Action>,
+    layout: Layout,
+) -> AttributeSet<evdev::RelativeAxisType> {
+    let mut keys = AttributeSet::<evdev::RelativeAxisType>::new();
+
+    let binds = actions.flat_map(|action| action.action.outputs(layout));
+
+    for bind in binds {
+        let key: evdev::RelativeAxisType = match bind {
+            Input::RelAxis(axis) => axis.into(),
+            _ => continue,
+        };
+
+        keys.insert(key);
+    }
+
+    keys
+}
+
+/// The absolute axes the actions can emit, with the ranges of the axes bound
+/// to them unless overridden by the action
+fn abs_axis_from_actions<'a>(
+    actions: impl Iterator<Item = (&'a DeviceId, &'a Action)>,
+    abs_info: &HashMap<DeviceId, HashMap<AbsAxis, AbsInfo>>,
+    layout: Layout,
+) -> HashMap<AbsAxis, AbsInfo> {
+    let mut abs_axis = HashMap::new();
+
+    for (device, action) in actions {
+        let source = match action.bind.trigger {
+            Input::AbsAxis(axis) => abs_info
+                .get(action.source(device))
+                .and_then(|abs_info| abs_info.get(&axis))
+                .copied(),
+            _ => None,
+        };
+
+        let overrides = match action.action {
+            ActionType::Bind { abs_info, .. } => abs_info,
+            _ => AxisInfo::default(),
+        };
+
+        for output in action.action.outputs(layout) {
+            if let Input::AbsAxis(axis) = output {
+                abs_axis
+                    .entry(axis)
+                    .or_insert_with(|| overrides.abs_info(source));
+            }
+        }
+    }
+
+    abs_axis
+}
+
+fn new_virtual_device(
+    config: Option<&Output>,
+    keys: &AttributeSet<evdev::Key>,
+    rel_axis: &AttributeSet<evdev::RelativeAxisType>,
+    abs_axis: &HashMap<AbsAxis, AbsInfo>,
+) -> VirtualDevice {
+    let name = config.map_or("CoMB Vitual Device", |config| config.name.as_str());
+
+    let mut builder = VirtualDeviceBuilder::new()
+        .unwrap()
+        .name(name)
+        .with_keys(keys)
+        .unwrap()
+        .with_relative_axes(rel_axis)
+        .unwrap();
+
+    if let Some(config) = config {
+        builder = builder.input_id(config.input_id());
+    }
+
+    for (&axis, &abs_info) in abs_axis {
+        builder = builder
+            .with_absolute_axis(&UinputAbsSetup::new(axis.into(), abs_info))
+            .unwrap();
+    }
+
+    builder.build().unwrap()
+}
+
+fn abs_range(abs_info: &AbsInfo) -> [i32; 5] {
+    [
+        abs_info.minimum(),
+        abs_info.maximum(),
+        abs_info.fuzz(),
+        abs_info.flat(),
+        abs_info.resolution(),
+    ]
+}
@@ -0,0 +1,149 @@
+use evdev::{uinput::VirtualDevice, InputEvent, InputEventKind, Synchronization};
+
+use crate::device::{DeviceEvent, DeviceInput};
+
+use super::ActionExecutor;
+
+/// The events of a grabbed device since its last `SYN_REPORT`, which are
+/// passed on together so that frames such as multi-touch contacts stay whole
+#[derive(Debug, Default)]
+pub(super) struct Frame(Vec<InputEvent>);
+impl Frame {
+    pub fn push(&mut self, event: InputEvent) {
+        self.0.push(event);
+    }
+
+    /// Adds a raw event of the device to the frame, returning the events of
+    /// the frame once it is complete
+    pub fn handle(&mut self, event: InputEvent) -> Option<Vec<InputEvent>> {
+        match event.kind() {
+            InputEventKind::Synchronization(Synchronization::SYN_REPORT) => self.take(),
+            // The rest of the frame was lost
+            InputEventKind::Synchronization(Synchronization::SYN_DROPPED) => {
+                self.0.clear();
+                None
+            }
+            _ => {
+                self.0.push(event);
+                None
+            }
+        }
+    }
+
+    pub fn take(&mut self) -> Option<Vec<InputEvent>> {
+        Some(std::mem::take(&mut self.0)).filter(|events| !events.is_empty())
+    }
+}
+
+/// The virtual device the unused events of a grabbed device are passed on
+/// through
+pub(super) struct Passthrough {
+    device: VirtualDevice,
+    frame: Frame,
+}
+impl Passthrough {
+    pub fn new(device: VirtualDevice) -> Self {
+        Self {
+            device,
+            frame: Frame::default(),
+        }
+    }
+
+    pub fn push(&mut self, event: InputEvent) {
+        self.frame.push(event);
+    }
+
+    /// Emits the events passed on since the last frame
+    pub fn flush(&mut self) {
+        if let Some(events) = self.frame.take() {
+            let _ = self.device.emit(&events);
+        }
+    }
+}
+
+impl ActionExecutor {
+    /// Passes the events of grabbed devices that are not inputs, like
+    /// switches, scan codes and multi-touch contacts, on, and ends their
+    /// frames with those of the inputs no action consumed
+    pub fn handle_event(&mut self, event: DeviceEvent) {
+        let Some(passthrough) = self.passthrough.get_mut(event.device()) else {
+            return;
+        };
+
+        // Inputs are passed on by `run_input` if no action consumes them
+        if DeviceInput::try_from(event.clone()).is_ok() {
+            return;
+        }
+
+        if let Some(events) = passthrough.frame.handle(event.raw()) {
+            let _ = passthrough.device.emit(&events);
+        }
+    }
+
+    /// Emits the events a timer passed on outside of the frames of a device
+    pub(super) fn flush_passthrough(&mut self, device: &str) {
+        if let Some(passthrough) = self.passthrough.get_mut(device) {
+            passthrough.flush();
+        }
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use evdev::{AbsoluteAxisType, EventType, Key, MiscType};
+
+    #[test]
+    fn frame_handle() {
+        let mut frame = Frame::default();
+
+        let scan = InputEvent::new(EventType::MISC, MiscType::MSC_SCAN.0, 30);
+        let key = InputEvent::new(EventType::KEY, Key::KEY_A.code(), 1);
+        let slot = InputEvent::new(EventType::ABSOLUTE, AbsoluteAxisType::ABS_MT_SLOT.0, 1);
+        let mt_report = InputEvent::new(
+            EventType::SYNCHRONIZATION,
+            Synchronization::SYN_MT_REPORT.0,
+            0,
+        );
+        let report = InputEvent::new(EventType::SYNCHRONIZATION, Synchronization::SYN_REPORT.0, 0);
+        let dropped = InputEvent::new(
+            EventType::SYNCHRONIZATION,
+            Synchronization::SYN_DROPPED.0,
+            0,
+        );
+
+        // Nothing is emitted for frames whose inputs were all consumed
+        assert!(frame.handle(report).is_none());
+
+        assert!(frame.handle(scan).is_none());
+        frame.push(key);
+        assert!(frame.handle(slot).is_none());
+        assert!(frame.handle(mt_report).is_none());
+
+        let events: Vec<_> = frame
+            .handle(report)
+            .unwrap()
+            .iter()
+            .map(|event| (event.kind(), event.value()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (scan.kind(), 30),
+                (key.kind(), 1),
+                (slot.kind(), 1),
+                (mt_report.kind(), 0)
+            ],
+            events
+        );
+        assert!(frame.take().is_none());
+
+        assert!(frame.handle(slot).is_none());
+        assert!(frame.handle(dropped).is_none());
+        assert!(frame.handle(report).is_none());
+
+        frame.push(key);
+        assert_eq!(1, frame.take().unwrap().len());
+    }
+}
@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use crate::{
+    config::ActionType,
+    input::{AbsAxis, Input},
+    timer::TimerId,
+};
+
+use super::{ActionExecutor, Timer};
+
+/// The deflection of the stick driving a pointer action
+#[derive(Debug, Default)]
+pub(super) struct PointerMotion {
+    /// Deflection of the bound axis and of its pair, between -1 and 1
+    deflection: [f64; 2],
+    /// Motion not yet emitted because it is less than one unit
+    remainder: [f64; 2],
+    /// Periodic timer emitting motion while the stick is deflected
+    timer: Option<TimerId>,
+}
+
+impl ActionExecutor {
+    /// Records the deflection of one of the axes of a pointer action, moving
+    /// the pointer while the stick is outside of the deadzone
+    pub(super) fn move_pointer(&mut self, device: &str, action: usize, input: Input, value: i32) {
+        let Some(ActionType::Pointer { motion, .. }) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| &action.action)
+        else {
+            return;
+        };
+
+        let Input::AbsAxis(axis) = input else {
+            return;
+        };
+
+        let is_pair = self.actions[device][action].bind.trigger != input;
+        let motion = *motion;
+
+        let source = self.actions[device][action].source(device);
+        let deflection = deflection(axis, self.axis_position(source, axis, value));
+
+        let state = self.states.entry(device.to_string()).or_default();
+        let pointer = state.pointers.entry(action).or_default();
+
+        pointer.deflection[is_pair as usize] = deflection;
+
+        let moving = motion.speed(magnitude(pointer.deflection)) > 0.0;
+
+        match (pointer.timer, moving) {
+            (None, true) => {
+                if !motion.tick_rate.is_finite() || motion.tick_rate <= 0.0 {
+                    println!(
+                        "Invalid pointer tick rate {}, expected ticks per second",
+                        motion.tick_rate
+                    );
+                    return;
+                }
+
+                let timer = self
+                    .scheduler
+                    .schedule_periodic(Duration::from_secs_f64(1.0 / motion.tick_rate));
+                self.timers
+                    .insert(timer, Timer::Pointer(device.to_string(), action));
+
+                pointer.timer = Some(timer);
+            }
+            (Some(timer), false) => {
+                self.scheduler.cancel(timer);
+                self.timers.remove(&timer);
+
+                pointer.timer = None;
+                pointer.remainder = [0.0; 2];
+            }
+            _ => {}
+        }
+    }
+
+    /// Emits the motion of a pointer action for one tick
+    pub(super) fn tick_pointer(&mut self, device: &str, action: usize) {
+        let Some((
+            ActionType::Pointer {
+                pointer: to,
+                pair,
+                motion,
+            },
+            output,
+        )) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| (&action.action, action.output.as_deref()))
+        else {
+            return;
+        };
+
+        let output = self.outputs.find(output);
+
+        let Some(pointer) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.pointers.get_mut(&action))
+        else {
+            return;
+        };
+
+        let targets = [Some(*to), pair.as_ref().map(|pair| pair.pointer)];
+
+        let magnitude = magnitude(pointer.deflection);
+
+        if magnitude == 0.0 {
+            return;
+        }
+
+        // The deadzone and acceleration apply to the deflection of the stick
+        // as a whole, so that diagonal motion keeps its direction
+        let distance = motion.speed(magnitude) / motion.tick_rate / magnitude;
+
+        for (idx, to) in targets.into_iter().enumerate() {
+            let Some(to) = to else {
+                continue;
+            };
+
+            let motion = pointer.deflection[idx] * distance + pointer.remainder[idx];
+            let value = motion.trunc();
+
+            pointer.remainder[idx] = motion - value;
+
+            if value != 0.0 {
+                Self::emit(self.outputs.device(output), to, value as i32);
+            }
+        }
+    }
+}
+
+/// The deflection of an axis between -1 and 1 from its position between 0
+/// and 1. Sticks are deflected from the middle of their range, triggers and
+/// pedals from their minimum.
+fn deflection(axis: AbsAxis, position: f64) -> f64 {
+    if axis.is_centred() {
+        position * 2.0 - 1.0
+    } else {
+        position
+    }
+}
+
+fn magnitude(deflection: [f64; 2]) -> f64 {
+    deflection[0].hypot(deflection[1]).min(1.0)
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::config::Motion;
+
+    #[test]
+    fn resting_trigger_does_not_move() {
+        let motion = Motion {
+            deadzone: Motion::deadzone(),
+            sensitivity: Motion::sensitivity(),
+            acceleration: Motion::acceleration(),
+            tick_rate: Motion::tick_rate(),
+        };
+
+        // Triggers rest at their minimum, sticks in the middle
+        let trigger = deflection(AbsAxis::Z, 0.0);
+        let stick = deflection(AbsAxis::X, 0.5);
+
+        assert_eq!(0.0, motion.speed(magnitude([trigger, 0.0])));
+        assert_eq!(0.0, motion.speed(magnitude([stick, 0.0])));
+
+        assert_eq!(1.0, deflection(AbsAxis::Z, 1.0));
+        assert_eq!(-1.0, deflection(AbsAxis::X, 0.0));
+    }
+}
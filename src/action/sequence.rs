@@ -0,0 +1,217 @@
+use std::{collections::HashSet, time::Duration};
+
+use crate::{
+    device::DeviceInput,
+    input::{Input, InputEvent, InputState},
+    timer::TimerId,
+};
+
+use super::{ActionExecutor, Timer};
+
+/// The steps of a sequence typed so far and the events held back while
+/// typing them
+#[derive(Debug)]
+pub(super) struct PendingSequence {
+    typed: Vec<Input>,
+    events: Vec<DeviceInput>,
+    timer: TimerId,
+}
+
+/// The inputs whose presses among the events have not been released yet
+fn held_steps(events: impl IntoIterator<Item = InputEvent>) -> HashSet<Input> {
+    let mut held = HashSet::new();
+
+    for event in events {
+        match InputState::from_i32(event.state()) {
+            Some(InputState::Pressed) => held.insert(event.input()),
+            Some(InputState::Released) => held.remove(&event.input()),
+            _ => continue,
+        };
+    }
+
+    held
+}
+
+impl ActionExecutor {
+    /// Holds back the events of inputs typed as the steps of a sequence until
+    /// either the whole sequence is typed, running its action instead of
+    /// theirs, or it can no longer be completed
+    pub(super) fn hold_sequence(&mut self, input: &DeviceInput) -> bool {
+        let device = input.device();
+        let event = input.input_event();
+
+        if !event.input().is_toggle() {
+            return false;
+        }
+
+        let Some(actions) = self.actions.get(device) else {
+            return false;
+        };
+
+        let source = self.device_names(input.source());
+        let state = self.states.entry(device.to_string()).or_default();
+
+        let input_state = InputState::from_i32(event.state());
+
+        // The trigger of a completed sequence is still held down
+        if input_state != Some(InputState::Pressed) {
+            if let Some(&action) = state.sequence_triggers.get(&event.input()) {
+                if input_state != Some(InputState::Repeated) {
+                    state.sequence_triggers.remove(&event.input());
+                }
+
+                self.dispatch(input, &[action]);
+                return true;
+            }
+
+            if state.sequence_steps.contains(&event.input()) {
+                if input_state != Some(InputState::Repeated) {
+                    state.sequence_steps.remove(&event.input());
+                }
+
+                return true;
+            }
+
+            // Releases of typed steps stay in order with their presses
+            return match &mut state.sequence {
+                Some(pending) if pending.typed.contains(&event.input()) => {
+                    pending.events.push(input.clone());
+                    true
+                }
+                _ => false,
+            };
+        }
+
+        let mut typed = state
+            .sequence
+            .as_ref()
+            .map(|pending| pending.typed.clone())
+            .unwrap_or_default();
+        typed.push(event.input());
+
+        let candidates: Vec<usize> = actions
+            .iter()
+            .enumerate()
+            .filter(|(_, action)| {
+                !action.bind.sequence.is_empty()
+                    && state.is_layer_active(action.layer.as_ref())
+                    && action.listens_to(device, &source)
+                    && self.input_values.allow(device, action, &self.names)
+                    && action
+                        .bind
+                        .steps()
+                        .take(typed.len())
+                        .eq(typed.iter().copied())
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+
+        if candidates.is_empty() {
+            if state.sequence.is_none() {
+                return false;
+            }
+
+            // The press may still start a sequence of its own
+            self.flush_sequence(device);
+            return self.hold_sequence(input);
+        }
+
+        let completed = candidates
+            .iter()
+            .copied()
+            .find(|&idx| actions[idx].bind.steps().count() == typed.len());
+
+        if let Some(action) = completed {
+            if let Some(pending) = state.sequence.take() {
+                self.scheduler.cancel(pending.timer);
+                self.timers.remove(&pending.timer);
+
+                state.sequence_steps.extend(held_steps(
+                    pending.events.iter().map(DeviceInput::input_event),
+                ));
+            }
+
+            // A step typed again as the last one is released as its trigger
+            state.sequence_steps.remove(&event.input());
+
+            state.sequence_triggers.insert(event.input(), action);
+
+            self.dispatch(input, &[action]);
+            return true;
+        }
+
+        match &mut state.sequence {
+            Some(pending) => {
+                pending.typed = typed;
+                pending.events.push(input.clone());
+            }
+            None => {
+                let timeout = candidates
+                    .iter()
+                    .map(|&idx| actions[idx].sequence_timeout)
+                    .max()
+                    .unwrap_or_default();
+
+                let timer = self.scheduler.schedule(Duration::from_millis(timeout));
+                self.timers
+                    .insert(timer, Timer::Sequence(device.to_string()));
+
+                state.sequence = Some(PendingSequence {
+                    typed,
+                    events: vec![input.clone()],
+                    timer,
+                });
+            }
+        }
+
+        true
+    }
+
+    /// Gives up on the pending sequence of a device, replaying the events it
+    /// held back
+    pub(super) fn flush_sequence(&mut self, device: &str) {
+        let Some(pending) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.sequence.take())
+        else {
+            return;
+        };
+
+        self.scheduler.cancel(pending.timer);
+        self.timers.remove(&pending.timer);
+
+        for input in pending.events {
+            self.run_input(input);
+        }
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::input::Key;
+
+    #[test]
+    fn held_steps_of_leader() {
+        let events = [
+            InputEvent::Key(Key::Capslock, InputState::Pressed),
+            InputEvent::Key(Key::G, InputState::Pressed),
+            InputEvent::Key(Key::Capslock, InputState::Repeated),
+            InputEvent::Key(Key::G, InputState::Released),
+        ];
+
+        assert_eq!(
+            HashSet::from([Input::Key(Key::Capslock)]),
+            held_steps(events)
+        );
+
+        let events = [
+            InputEvent::Key(Key::G, InputState::Pressed),
+            InputEvent::Key(Key::G, InputState::Released),
+        ];
+
+        assert!(held_steps(events).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    config::TapHoldFlavor,
+    device::DeviceInput,
+    input::{Input, InputState},
+    timer::TimerId,
+};
+
+use super::{ActionExecutor, DeviceState, OutputId, Outputs};
+
+/// A pressed tap-hold action that has neither been released nor held past its
+/// tapping term
+#[derive(Debug)]
+pub(super) struct PendingTapHold {
+    pub input: Input,
+    /// The output the tap and hold targets are emitted through
+    pub output: OutputId,
+    pub tap: Input,
+    pub hold: Input,
+    pub flavor: TapHoldFlavor,
+    pub timer: TimerId,
+}
+
+impl ActionExecutor {
+    /// Holds back keys and buttons pressed on a device while one of its
+    /// tap-hold actions is pending, so that their bindings run after the
+    /// tap-hold action is resolved. Depending on the flavor of the tap-hold
+    /// action the interruption resolves it as a hold straight away.
+    pub(super) fn hold_back(&mut self, input: &DeviceInput) -> bool {
+        let Some(state) = self.states.get_mut(input.device()) else {
+            return false;
+        };
+
+        let Some(pending) = &state.tap_hold else {
+            return false;
+        };
+
+        let event = input.input_event();
+
+        if !event.input().is_toggle() || event.input() == pending.input {
+            return false;
+        }
+
+        let hold = match (pending.flavor, InputState::from_i32(event.state())) {
+            (TapHoldFlavor::HoldOnOtherKeyPress, Some(InputState::Pressed)) => true,
+            // The input has been both pressed and released within the
+            // tapping term
+            (TapHoldFlavor::PermissiveHold, Some(InputState::Released)) => state
+                .held_back
+                .iter()
+                .any(|held_back| held_back.input_event().input() == event.input()),
+            _ => false,
+        };
+
+        state.held_back.push(input.clone());
+
+        if hold {
+            self.resolve_tap_hold(input.device(), true);
+        }
+
+        true
+    }
+
+    pub(super) fn resolve_tap_hold(&mut self, device: &str, hold: bool) {
+        let Some(state) = self.states.get_mut(device) else {
+            return;
+        };
+
+        let Some(pending) = state.tap_hold.take() else {
+            return;
+        };
+
+        self.scheduler.cancel(pending.timer);
+        self.timers.remove(&pending.timer);
+
+        if hold {
+            state
+                .tap_hold_held
+                .insert(pending.input, (pending.output, pending.hold));
+
+            Self::emit(self.outputs.device(pending.output), pending.hold, 1);
+        } else {
+            Self::emit(self.outputs.device(pending.output), pending.tap, 1);
+            Self::emit(self.outputs.device(pending.output), pending.tap, 0);
+        }
+
+        self.release_held_back(device);
+    }
+
+    pub(super) fn release_device_tap_holds(outputs: &mut Outputs, state: &mut DeviceState) {
+        for (_, (output, hold)) in state.tap_hold_held.drain() {
+            Self::emit(outputs.device(output), hold, 0);
+        }
+    }
+
+    pub(super) fn release_tap_holds(&mut self) {
+        for state in self.states.values_mut() {
+            Self::release_device_tap_holds(&mut self.outputs, state);
+        }
+    }
+
+    /// Replays the inputs held back on a device once it no longer has a
+    /// pending tap-hold action
+    pub(super) fn release_held_back(&mut self, device: &str) {
+        let Some(state) = self.states.get_mut(device) else {
+            return;
+        };
+
+        if state.tap_hold.is_some() {
+            return;
+        }
+
+        for input in std::mem::take(&mut state.held_back) {
+            self.run_input(input);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use evdev::AbsInfo;
+
+use crate::config::Transform;
+
+use super::ActionExecutor;
+
+impl ActionExecutor {
+    /// Transforms the value of an axis bound to another axis. Absolute axes
+    /// are mapped from the range of the source to the range of the target,
+    /// anything else is treated as relative motion.
+    pub(super) fn transform_axis(
+        transform: &Transform,
+        centred: bool,
+        source: Option<&AbsInfo>,
+        target: Option<&AbsInfo>,
+        remainder: &mut f64,
+        value: i32,
+    ) -> i32 {
+        if let (Some(source), Some(target)) = (source, target) {
+            return transform.apply_abs(value, centred, source, target);
+        }
+
+        let motion = transform.apply_rel(value as f64) + *remainder;
+        let value = motion.trunc();
+
+        *remainder = motion - value;
+
+        transform.clamp(value as i32)
+    }
+
+    /// Whether the value of an absolute axis has to be mapped into the range
+    /// of its target, such as an axis of a gamepad profile
+    pub(super) fn rescales(source: Option<&AbsInfo>, target: Option<&AbsInfo>) -> bool {
+        match (source, target) {
+            (Some(source), Some(target)) => {
+                (source.minimum(), source.maximum()) != (target.minimum(), target.maximum())
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use crate::{config::ActionType, input::Input, timer::TimerId};
+
+use super::{ActionExecutor, OutputId, Timer};
+
+/// A turbo binding whose source is held down
+#[derive(Debug)]
+pub(super) struct Autofire {
+    to: Input,
+    output: OutputId,
+    /// Periodic timer pressing the target
+    timer: TimerId,
+    /// Timer releasing the target, while it is pressed
+    release_timer: Option<TimerId>,
+    /// How long each press of the target lasts
+    hold: Duration,
+}
+
+impl ActionExecutor {
+    /// Starts or stops repeatedly pressing the target of a turbo binding
+    pub(super) fn set_turbo(&mut self, device: &str, action: usize, active: bool) {
+        let playing = self
+            .states
+            .get(device)
+            .is_some_and(|state| state.turbo.contains_key(&action));
+
+        match (playing, active) {
+            (false, true) => self.start_turbo(device, action),
+            (true, false) => self.stop_turbo(device, action),
+            _ => {}
+        }
+    }
+
+    fn start_turbo(&mut self, device: &str, action: usize) {
+        let Some((
+            ActionType::Bind {
+                to,
+                rate,
+                duty_cycle,
+                ..
+            },
+            output,
+        )) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| (&action.action, action.output.as_deref()))
+        else {
+            return;
+        };
+
+        let output = self.outputs.find(output);
+
+        if !rate.is_finite() || *rate <= 0.0 {
+            println!("Invalid turbo rate {}, expected presses per second", rate);
+            return;
+        }
+
+        let period = Duration::from_secs_f64(1.0 / rate);
+        let hold = period.mul_f64(duty_cycle.clamp(0.0, 1.0));
+
+        let timer = self.scheduler.schedule_periodic(period);
+        self.timers
+            .insert(timer, Timer::Turbo(device.to_string(), action));
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        state.turbo.insert(
+            action,
+            Autofire {
+                to: *to,
+                output,
+                timer,
+                release_timer: None,
+                hold,
+            },
+        );
+
+        self.fire_turbo(device, action);
+    }
+
+    /// Presses the target of a turbo binding, releasing it once its press
+    /// has lasted for the duty cycle
+    pub(super) fn fire_turbo(&mut self, device: &str, action: usize) {
+        let Some(autofire) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.turbo.get_mut(&action))
+        else {
+            return;
+        };
+
+        // The previous press is still held down with a duty cycle close to 1
+        if let Some(release_timer) = autofire.release_timer.take() {
+            self.scheduler.cancel(release_timer);
+            self.timers.remove(&release_timer);
+
+            Self::emit(self.outputs.device(autofire.output), autofire.to, 0);
+        }
+
+        Self::emit(self.outputs.device(autofire.output), autofire.to, 1);
+
+        let release_timer = self.scheduler.schedule(autofire.hold);
+        self.timers.insert(
+            release_timer,
+            Timer::TurboRelease(device.to_string(), action),
+        );
+
+        autofire.release_timer = Some(release_timer);
+    }
+
+    pub(super) fn release_turbo(&mut self, device: &str, action: usize) {
+        let Some(autofire) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.turbo.get_mut(&action))
+        else {
+            return;
+        };
+
+        if autofire.release_timer.take().is_some() {
+            Self::emit(self.outputs.device(autofire.output), autofire.to, 0);
+        }
+    }
+
+    pub(super) fn stop_turbo(&mut self, device: &str, action: usize) {
+        let Some(autofire) = self
+            .states
+            .get_mut(device)
+            .and_then(|state| state.turbo.remove(&action))
+        else {
+            return;
+        };
+
+        self.scheduler.cancel(autofire.timer);
+        self.timers.remove(&autofire.timer);
+
+        if let Some(release_timer) = autofire.release_timer {
+            self.scheduler.cancel(release_timer);
+            self.timers.remove(&release_timer);
+
+            Self::emit(self.outputs.device(autofire.output), autofire.to, 0);
+        }
+    }
+
+    pub(super) fn stop_turbos(&mut self) {
+        let playing = self
+            .states
+            .iter()
+            .flat_map(|(device, state)| state.turbo.keys().map(|&action| (device.clone(), action)))
+            .collect::<Vec<_>>();
+
+        for (device, action) in playing {
+            self.stop_turbo(&device, action);
+        }
+    }
+}
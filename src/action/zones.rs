@@ -0,0 +1,63 @@
+use crate::{config::ActionType, input::Input};
+
+use super::{ActionExecutor, DeviceState, Outputs};
+
+impl ActionExecutor {
+    /// Moves a zones action to the zone the axis is in, releasing the target
+    /// of the zone it leaves and pressing the target of the zone it enters
+    pub(super) fn update_zone(&mut self, device: &str, action: usize, input: Input, value: i32) {
+        let Input::AbsAxis(axis) = input else {
+            return;
+        };
+
+        let source = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map_or(device, |action| action.source(device));
+
+        let position = self.axis_position(source, axis, value);
+
+        let Some((ActionType::Zones { zones, hysteresis }, output)) = self
+            .actions
+            .get(device)
+            .and_then(|actions| actions.get(action))
+            .map(|action| (&action.action, action.output.as_deref()))
+        else {
+            return;
+        };
+
+        let output = self.outputs.find(output);
+
+        let state = self.states.entry(device.to_string()).or_default();
+
+        if let Some(&(current, output, to)) = state.zones.get(&action) {
+            // Small movements around the edge of a zone do not leave it
+            if zones[current].contains(position, *hysteresis) {
+                return;
+            }
+
+            state.zones.remove(&action);
+            Self::emit(self.outputs.device(output), to, 0);
+        }
+
+        if let Some(entered) = zones.iter().position(|zone| zone.contains(position, 0.0)) {
+            let to = zones[entered].to;
+
+            state.zones.insert(action, (entered, output, to));
+            Self::emit(self.outputs.device(output), to, 1);
+        }
+    }
+
+    pub(super) fn release_device_zones(outputs: &mut Outputs, state: &mut DeviceState) {
+        for (_, (_, output, to)) in state.zones.drain() {
+            Self::emit(outputs.device(output), to, 0);
+        }
+    }
+
+    pub(super) fn release_zones(&mut self) {
+        for state in self.states.values_mut() {
+            Self::release_device_zones(&mut self.outputs, state);
+        }
+    }
+}
@@ -1,49 +1,524 @@
 use std::{fmt::Display, str::FromStr};
 
+use evdev::AbsInfo;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    device::DeviceAccessor,
-    input::{Input, InputState},
+    device::{DeviceAccessor, DeviceId, DeviceNames, DeviceSlot},
+    input::{Btn, Chord, Input, InputState, Key, Layout, RelAxis},
 };
 
-use super::util::display_from_str;
+use super::{
+    hook::Hook,
+    macros::{MacroPolicy, MacroStep},
+    transform::Transform,
+    util::{chord_or_sequence, display_from_str, display_from_str_option},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Device {
     #[serde(flatten)]
     pub accessor: DeviceAccessor,
+    /// Whether the device is grabbed, its events only reaching other
+    /// applications through CoMB
+    #[serde(default)]
+    pub grab: bool,
+    /// Player slot of the device, so that several devices matched by the
+    /// same accessor each get their own actions
+    #[serde(default)]
+    pub player: Option<u8>,
+    /// Name of the output the actions emit through unless they name one of
+    /// their own
+    #[serde(default)]
+    pub output: Option<String>,
     #[serde(default)]
     pub actions: Vec<Action>,
+    #[serde(default)]
+    pub layers: Vec<Layer>,
 }
+impl Device {
+    pub fn slot(&self) -> DeviceSlot {
+        DeviceSlot {
+            accessor: self.accessor.clone(),
+            player: self.player,
+        }
+    }
+
+    /// The id the actions of the device are found by
+    pub fn id(&self) -> DeviceId {
+        self.slot().id()
+    }
+
+    /// The actions of the device followed by the actions of each of its
+    /// layers
+    pub fn into_actions(self) -> Vec<Action> {
+        let layers = self.layers.into_iter().flat_map(|layer| {
+            let name = layer.name;
 
+            layer.actions.into_iter().map(move |mut action| {
+                action.layer = Some(name.clone());
+                action
+            })
+        });
+
+        let output = self.output;
+
+        self.actions
+            .into_iter()
+            .chain(layers)
+            .map(|mut action| {
+                if action.output.is_none() {
+                    action.output = output.clone();
+                }
+
+                action
+            })
+            .collect()
+    }
+}
+
+/// A named set of actions that take precedence over the actions of the
+/// device while the layer is active
 #[derive(Debug, Serialize, Deserialize)]
+pub struct Layer {
+    pub name: String,
+    #[serde(default)]
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Action {
-    #[serde(with = "display_from_str")]
-    pub bind: Input,
+    #[serde(with = "chord_or_sequence")]
+    pub bind: Chord,
+    #[serde(default)]
+    pub modifiers: ModifierMode,
+    /// Whether the inputs of the chord form a combo, pressed together in any
+    /// order instead of the modifiers first
+    #[serde(default)]
+    pub combo: bool,
+    /// Time in milliseconds within which every input of a combo has to be
+    /// pressed
+    #[serde(default = "Action::combo_term")]
+    pub combo_term: u64,
+    /// Time in milliseconds within which every input of a sequence has to be
+    /// pressed, starting from the first
+    #[serde(default = "Action::sequence_timeout")]
+    pub sequence_timeout: u64,
+    /// Only runs the action while an input is in the given state
+    #[serde(default, with = "display_from_str_option")]
+    pub r#while: Option<Guard>,
+    /// Only runs the action while an input is not in the given state
+    #[serde(default, with = "display_from_str_option")]
+    pub unless: Option<Guard>,
+    /// The device whose inputs the guards test, the device of the action if
+    /// `None`
+    #[serde(default)]
+    pub guard_device: Option<String>,
+    /// The configured device whose events trigger the action, the device of
+    /// its section if `None`
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Name of the output the action emits through, the default virtual
+    /// device if `None`
+    #[serde(default)]
+    pub output: Option<String>,
     #[serde(flatten)]
     pub action: ActionType,
+    /// The layer the action belongs to, `None` for the base layer
+    #[serde(skip)]
+    pub layer: Option<String>,
 }
+impl Action {
+    pub fn combo_term() -> u64 {
+        50
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
+    pub fn sequence_timeout() -> u64 {
+        1000
+    }
+
+    /// The inputs of a combo, in no particular order
+    pub fn combo_inputs(&self) -> Vec<Input> {
+        let mut inputs = self.bind.modifiers.clone();
+        inputs.push(self.bind.trigger);
+        inputs
+    }
+
+    /// Whether the while and unless guards of the action hold, given the
+    /// current value of each input of the guard device
+    pub fn guards_hold(&self, value: impl Fn(Input) -> i32) -> bool {
+        let holds = |guard: &Guard| guard.test(value(guard.input));
+
+        self.r#while.as_ref().is_none_or(holds) && !self.unless.as_ref().is_some_and(holds)
+    }
+
+    /// The device whose events trigger the action, given the device of its
+    /// section
+    pub fn source<'a>(&'a self, section: &'a str) -> &'a str {
+        self.from.as_deref().unwrap_or(section)
+    }
+
+    /// Whether the events of a device trigger the action, given the device
+    /// of its section
+    pub fn listens_to(&self, section: &str, device: &DeviceNames) -> bool {
+        match &self.from {
+            Some(from) => device.matches(from),
+            None => device.id() == section,
+        }
+    }
+
+    /// Whether events of an input of a device trigger, modify or guard the
+    /// action, given the device of its section
+    pub fn depends_on(&self, section: &str, device: &DeviceNames, input: Input) -> bool {
+        let bound = self.listens_to(section, device)
+            && (self.is_triggered_by(input)
+                || self.bind.modifiers.contains(&input)
+                || self.bind.sequence.contains(&input));
+
+        let guarded = self.guarded_by(section, device)
+            && [self.r#while, self.unless]
+                .iter()
+                .flatten()
+                .any(|guard| guard.input == input);
+
+        bound || guarded
+    }
+
+    /// Whether the guards of the action test the inputs of a device, given
+    /// the device of its section
+    pub fn guarded_by(&self, section: &str, device: &DeviceNames) -> bool {
+        match &self.guard_device {
+            Some(guard_device) => device.matches(guard_device),
+            None => device.id() == section,
+        }
+    }
+
+    /// Whether events of the input reach the action, provided the modifiers
+    /// of its chord are held
+    pub fn is_triggered_by(&self, input: Input) -> bool {
+        match &self.action {
+            ActionType::Pointer {
+                pair: Some(pair), ..
+            } if pair.bind == input => true,
+            _ => self.bind.trigger == input,
+        }
+    }
+}
+
+/// What happens to the modifier inputs of a chord binding
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModifierMode {
+    /// The modifiers keep running any bindings of their own
+    #[default]
+    Passthrough,
+    /// The modifiers are reserved for the chord and run no bindings of their
+    /// own
+    Consume,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged, rename_all = "snake_case")]
 pub enum ActionType {
     Hook {
         #[serde(default = "WhenCondition::pressed")]
         when: WhenCondition,
-        cmd: String,
+        #[serde(flatten)]
+        hook: Hook,
     },
     Bind {
         #[serde(with = "display_from_str")]
         to: Input,
         #[serde(default)]
         when: Option<WhenCondition>,
+        #[serde(default)]
+        mode: BindMode,
+        /// Presses per second of a turbo binding
+        #[serde(default = "BindMode::rate")]
+        rate: f64,
+        /// Fraction of each turbo press the target is held down for
+        #[serde(default = "BindMode::duty_cycle")]
+        duty_cycle: f64,
+        /// Value of an axis target while a key or button source is held
+        /// down. The deflection between -1 and 1 of an absolute axis, or the
+        /// factor applied to the speed of a relative axis.
+        #[serde(default = "BindMode::value")]
+        value: f64,
+        /// Time in milliseconds an axis driven by a key or button takes to
+        /// move from its centre to full deflection, or to reach full speed
+        #[serde(default)]
+        ramp: u64,
+        /// Speed of a relative axis driven by a key or button
+        #[serde(flatten)]
+        motion: Motion,
+        /// Range of an absolute axis target
+        #[serde(flatten)]
+        abs_info: AxisInfo,
+        #[serde(flatten)]
+        transform: Transform,
+    },
+    Macro {
+        to: Vec<MacroStep>,
+        #[serde(default = "WhenCondition::pressed")]
+        when: WhenCondition,
+        #[serde(default)]
+        policy: MacroPolicy,
     },
     Print {
         #[serde(default = "WhenCondition::pressed")]
         when: WhenCondition,
         print: String,
     },
+    TapHold {
+        #[serde(with = "display_from_str")]
+        tap: Input,
+        #[serde(with = "display_from_str")]
+        hold: Input,
+        /// Time in milliseconds after which a press counts as a hold
+        #[serde(default = "TapHoldFlavor::tapping_term")]
+        tapping_term: u64,
+        #[serde(default)]
+        flavor: TapHoldFlavor,
+    },
+    Layer {
+        #[serde(flatten)]
+        layer: LayerAction,
+        #[serde(default = "WhenCondition::pressed")]
+        when: WhenCondition,
+    },
+    Pointer {
+        #[serde(with = "display_from_str")]
+        pointer: Input,
+        /// The other axis of the stick, moving a second relative axis
+        #[serde(default)]
+        pair: Option<PointerPair>,
+        #[serde(flatten)]
+        motion: Motion,
+    },
+    Zones {
+        zones: Vec<Zone>,
+        /// How far, as a fraction of the axis' range, the axis has to leave
+        /// a zone before it is released
+        #[serde(default = "Zone::hysteresis")]
+        hysteresis: f64,
+    },
+}
+
+impl ActionType {
+    /// The inputs the action can emit on the virtual device
+    pub fn outputs(&self, layout: Layout) -> Vec<Input> {
+        match self {
+            Self::Hook { .. } => vec![],
+            Self::Print { print, .. } => print
+                .chars()
+                .filter_map(|c| layout.key(c))
+                .flat_map(|(key, shift)| {
+                    let shift = shift.then_some(Input::Key(Key::Leftshift));
+
+                    std::iter::once(Input::Key(key)).chain(shift)
+                })
+                .collect(),
+            Self::Bind { to, .. } => vec![*to],
+            Self::Macro { to, .. } => to.iter().filter_map(MacroStep::input).collect(),
+            Self::TapHold { tap, hold, .. } => vec![*tap, *hold],
+            Self::Layer { .. } => vec![],
+            Self::Pointer { pointer, pair, .. } => {
+                let mut outputs = vec![*pointer];
+                outputs.extend(pair.as_ref().map(|pair| pair.pointer));
+
+                // Without a button the virtual device is not recognised as a
+                // mouse
+                if outputs.iter().any(|output| {
+                    matches!(output, Input::RelAxis(RelAxis::X) | Input::RelAxis(RelAxis::Y))
+                }) {
+                    outputs.push(Input::Btn(Btn::Left));
+                }
+
+                outputs
+            }
+            Self::Zones { zones, .. } => zones.iter().map(|zone| zone.to).collect(),
+        }
+    }
+
+    pub fn gesture(&self) -> Option<Gesture> {
+        match self {
+            Self::Hook { when, .. }
+            | Self::Print { when, .. }
+            | Self::Macro { when, .. }
+            | Self::Layer { when, .. } => when.gesture(),
+            Self::Bind { when, .. } => when.and_then(|when| when.gesture()),
+            Self::TapHold { .. } | Self::Pointer { .. } | Self::Zones { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PointerPair {
+    #[serde(with = "display_from_str")]
+    pub bind: Input,
+    #[serde(with = "display_from_str")]
+    pub pointer: Input,
+}
+
+/// How the deflection of an absolute axis turns into continuous relative
+/// motion
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Motion {
+    /// Fraction of the deflection ignored around the centre
+    #[serde(default = "Motion::deadzone")]
+    pub deadzone: f64,
+    /// Units of motion per second at full deflection
+    #[serde(default = "Motion::sensitivity")]
+    pub sensitivity: f64,
+    /// Exponent applied to the deflection, values above 1 giving finer
+    /// control near the centre
+    #[serde(default = "Motion::acceleration")]
+    pub acceleration: f64,
+    /// Number of times per second motion is emitted
+    #[serde(default = "Motion::tick_rate")]
+    pub tick_rate: f64,
+}
+impl Motion {
+    pub fn deadzone() -> f64 {
+        0.1
+    }
+
+    pub fn sensitivity() -> f64 {
+        1000.0
+    }
+
+    pub fn acceleration() -> f64 {
+        2.0
+    }
+
+    pub fn tick_rate() -> f64 {
+        100.0
+    }
+
+    /// The speed, in units per second, of a deflection between 0 and 1
+    pub fn speed(&self, deflection: f64) -> f64 {
+        let deadzone = self.deadzone.clamp(0.0, 0.99);
+
+        if deflection <= deadzone {
+            return 0.0;
+        }
+
+        let deflection = ((deflection - deadzone) / (1.0 - deadzone)).min(1.0);
+
+        self.sensitivity * deflection.powf(self.acceleration)
+    }
+}
+
+/// Overrides of the range of an absolute axis of the virtual device, the
+/// rest being copied from the source axis
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct AxisInfo {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+    pub fuzz: Option<i32>,
+    pub flat: Option<i32>,
+    pub resolution: Option<i32>,
+}
+impl AxisInfo {
+    pub fn abs_info(&self, source: Option<AbsInfo>) -> AbsInfo {
+        let source =
+            source.unwrap_or_else(|| AbsInfo::new(0, i16::MIN as i32, i16::MAX as i32, 0, 0, 0));
+
+        AbsInfo::new(
+            source.value(),
+            self.min.unwrap_or(source.minimum()),
+            self.max.unwrap_or(source.maximum()),
+            self.fuzz.unwrap_or(source.fuzz()),
+            self.flat.unwrap_or(source.flat()),
+            self.resolution.unwrap_or(source.resolution()),
+        )
+    }
+}
+
+/// A range of positions of an absolute axis, 0 being its minimum and 1 its
+/// maximum, in which the target is held down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Zone {
+    #[serde(default)]
+    pub min: f64,
+    #[serde(default = "Zone::max")]
+    pub max: f64,
+    #[serde(with = "display_from_str")]
+    pub to: Input,
+}
+impl Zone {
+    pub fn max() -> f64 {
+        1.0
+    }
+
+    pub fn hysteresis() -> f64 {
+        0.05
+    }
+
+    pub fn contains(&self, position: f64, margin: f64) -> bool {
+        self.min - margin <= position && position <= self.max + margin
+    }
+}
+
+/// How the target of a binding follows its source
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BindMode {
+    /// The target is held down while the source is
+    #[default]
+    Hold,
+    /// Each press of the source alternately holds down and releases the
+    /// target
+    Toggle,
+    /// The target is repeatedly pressed and released while the source is
+    /// held down
+    Turbo,
+}
+impl BindMode {
+    pub fn rate() -> f64 {
+        10.0
+    }
+
+    pub fn duty_cycle() -> f64 {
+        0.5
+    }
+
+    pub fn value() -> f64 {
+        1.0
+    }
+}
+
+/// Switches the layers of a device, each variant naming the layer
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LayerAction {
+    #[serde(rename = "layer_on")]
+    On(String),
+    #[serde(rename = "layer_off")]
+    Off(String),
+    #[serde(rename = "layer_toggle")]
+    Toggle(String),
+    /// The layer is active while the input is held
+    #[serde(rename = "layer_momentary")]
+    Momentary(String),
+}
+
+/// Decides when an interrupted tap-hold action is resolved as a hold
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TapHoldFlavor {
+    /// Only holding the input past the tapping term counts as a hold
+    #[default]
+    TapPreferred,
+    /// Pressing another input before the tapping term counts as a hold
+    HoldOnOtherKeyPress,
+    /// Pressing and releasing another input before the tapping term counts
+    /// as a hold
+    PermissiveHold,
+}
+impl TapHoldFlavor {
+    pub fn tapping_term() -> u64 {
+        200
+    }
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -51,21 +526,107 @@ pub enum ActionType {
 pub enum WhenCondition {
     InputState(#[serde(with = "display_from_str")] InputState),
     Condition(#[serde(with = "display_from_str")] Condition),
+    Edge(#[serde(with = "display_from_str")] EdgeCondition),
+    Gesture(#[serde(with = "display_from_str")] Gesture),
 }
 impl WhenCondition {
     pub fn pressed() -> Self {
         Self::InputState(InputState::Pressed)
     }
 
+    pub fn gesture(&self) -> Option<Gesture> {
+        match self {
+            Self::Gesture(gesture) => Some(*gesture),
+            _ => None,
+        }
+    }
+
     pub fn test(&self, value: i32) -> bool {
         match self {
             // if let guards are unstable
             // (issue #51114 https://github.com/rust-lang/rust/issues/51114)
             Self::InputState(state) if let Some(value) = InputState::from_i32(value) => state == &value,
             Self::Condition(condition) => condition.test(value),
+            Self::Edge(EdgeCondition(_, condition)) => condition.test(value),
             _ => false,
         }
     }
+
+    /// Tests the new value of an input, edge conditions also depending on
+    /// its previous value
+    pub fn test_edge(&self, previous: Option<i32>, value: i32) -> bool {
+        match self {
+            Self::Edge(edge) => edge.test(previous, value),
+            _ => self.test(value),
+        }
+    }
+}
+
+/// A condition on how an input is pressed over time rather than on its value
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Gesture {
+    /// The input is tapped twice, each tap following the previous one within
+    /// the given number of milliseconds
+    DoubleTap(u64),
+    TripleTap(u64),
+    /// The input is held down for the given number of milliseconds
+    Hold(u64),
+}
+impl Gesture {
+    pub fn tap_window() -> u64 {
+        200
+    }
+
+    pub fn taps(&self) -> Option<u8> {
+        match self {
+            Self::DoubleTap(_) => Some(2),
+            Self::TripleTap(_) => Some(3),
+            Self::Hold(_) => None,
+        }
+    }
+
+    /// The time within which the next tap of a multi-tap gesture has to
+    /// follow
+    pub fn window(&self) -> Option<u64> {
+        match self {
+            Self::DoubleTap(window) | Self::TripleTap(window) => Some(*window),
+            Self::Hold(_) => None,
+        }
+    }
+}
+
+/// Parses a duration in milliseconds, with or without a `ms` suffix
+fn parse_ms(s: &str) -> Result<u64, ()> {
+    let ms = s.trim();
+    let ms = ms.strip_suffix("ms").unwrap_or(ms);
+
+    ms.parse().map_err(|_| ())
+}
+impl FromStr for Gesture {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, ms) = match s.split_once(':') {
+            Some((name, ms)) => (name.trim(), Some(parse_ms(ms)?)),
+            None => (s.trim(), None),
+        };
+
+        match (name, ms) {
+            ("double_tap", ms) => Ok(Self::DoubleTap(ms.unwrap_or(Self::tap_window()))),
+            ("triple_tap", ms) => Ok(Self::TripleTap(ms.unwrap_or(Self::tap_window()))),
+            ("hold", Some(ms)) => Ok(Self::Hold(ms)),
+            _ => Err(()),
+        }
+    }
+}
+impl Display for Gesture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DoubleTap(window) => write!(f, "double_tap:{}", window),
+            Self::TripleTap(window) => write!(f, "triple_tap:{}", window),
+            Self::Hold(ms) => write!(f, "hold:{}", ms),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -142,3 +703,423 @@ impl Display for Condition {
         write!(f, "{}{}", self.0, self.1)
     }
 }
+
+/// The state of an input an action depends on, e.g. `btn:tl` for the button
+/// being held down or `abs_axis:z>200`. Relative axes have no state to
+/// depend on.
+#[derive(Debug, Copy, Clone)]
+pub struct Guard {
+    pub input: Input,
+    /// Tested against the value of the input, which otherwise has to be
+    /// non-zero
+    pub condition: Option<Condition>,
+}
+impl Guard {
+    pub fn test(&self, value: i32) -> bool {
+        match self.condition {
+            Some(condition) => condition.test(value),
+            None => value != 0,
+        }
+    }
+}
+impl FromStr for Guard {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (input, condition) = match s.find(['<', '>', '=', '!']) {
+            Some(idx) => (&s[..idx], Some(s[idx..].trim().parse()?)),
+            None => (s, None),
+        };
+
+        let input = input.trim().parse()?;
+
+        if let Input::RelAxis(_) = input {
+            return Err(());
+        }
+
+        Ok(Self { input, condition })
+    }
+}
+impl Display for Guard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.input)?;
+
+        if let Some(condition) = self.condition {
+            write!(f, "{}", condition)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Edge {
+    /// The condition becomes true
+    Rising,
+    /// The condition becomes false
+    Falling,
+    /// The condition becomes either true or false
+    Crossing,
+}
+impl FromStr for Edge {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "rising" => Ok(Self::Rising),
+            "falling" => Ok(Self::Falling),
+            "crossing" => Ok(Self::Crossing),
+            _ => Err(()),
+        }
+    }
+}
+impl Display for Edge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rising => write!(f, "rising"),
+            Self::Falling => write!(f, "falling"),
+            Self::Crossing => write!(f, "crossing"),
+        }
+    }
+}
+
+/// A condition that only holds when its result changes between two values of
+/// an input, e.g. `rising:>30000`
+#[derive(Debug, Copy, Clone)]
+pub struct EdgeCondition(Edge, Condition);
+impl EdgeCondition {
+    /// Tests the new value of an input against its previous value, the
+    /// condition being false before the input has a value
+    pub fn test(&self, previous: Option<i32>, value: i32) -> bool {
+        let was = previous.is_some_and(|previous| self.1.test(previous));
+        let is = self.1.test(value);
+
+        match self.0 {
+            Edge::Rising => !was && is,
+            Edge::Falling => was && !is,
+            Edge::Crossing => was != is,
+        }
+    }
+}
+impl FromStr for EdgeCondition {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (edge, condition) = s.split_once(':').ok_or(())?;
+
+        Ok(Self(edge.parse()?, condition.trim().parse()?))
+    }
+}
+impl Display for EdgeCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.0, self.1)
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn gesture_from_str() {
+        assert_eq!(Ok(Gesture::DoubleTap(200)), "double_tap".parse());
+        assert_eq!(Ok(Gesture::DoubleTap(300)), "double_tap:300ms".parse());
+        assert_eq!(Ok(Gesture::TripleTap(200)), "triple_tap".parse());
+        assert_eq!(Ok(Gesture::Hold(500)), "hold:500".parse());
+        assert_eq!(Ok(Gesture::Hold(500)), "hold: 500ms".parse());
+        assert_eq!(Err(()), "hold".parse::<Gesture>());
+        assert_eq!("hold:250", Gesture::Hold(250).to_string());
+    }
+
+    #[test]
+    fn edge_condition() {
+        let rising: EdgeCondition = "rising:>100".parse().unwrap();
+        let falling: EdgeCondition = "falling:>100".parse().unwrap();
+        let crossing: EdgeCondition = "crossing: >100".parse().unwrap();
+
+        assert!(rising.test(None, 200));
+        assert!(rising.test(Some(50), 200));
+        assert!(!rising.test(Some(150), 200));
+        assert!(!rising.test(Some(200), 50));
+
+        assert!(falling.test(Some(200), 50));
+        assert!(!falling.test(None, 50));
+        assert!(!falling.test(Some(50), 20));
+
+        assert!(crossing.test(Some(50), 200));
+        assert!(crossing.test(Some(200), 50));
+        assert!(!crossing.test(Some(200), 150));
+
+        assert_eq!("rising:>100", rising.to_string());
+        assert!("rising".parse::<EdgeCondition>().is_err());
+    }
+
+    #[test]
+    fn layer_from_toml() {
+        let device: Device = toml::from_str(
+            r#"
+            name = "gamepad"
+
+            [[actions]]
+            bind = "btn:mode"
+            layer_momentary = "media"
+
+            [[layers]]
+            name = "media"
+
+            [[layers.actions]]
+            bind = "btn:south"
+            to = "key:playpause"
+            "#,
+        )
+        .unwrap();
+
+        let actions = device.into_actions();
+
+        assert!(matches!(
+            &actions[0].action,
+            ActionType::Layer { layer: LayerAction::Momentary(name), .. } if name == "media"
+        ));
+        assert_eq!(None, actions[0].layer);
+        assert_eq!(Some("media".to_string()), actions[1].layer);
+    }
+
+    #[test]
+    fn combo_from_toml() {
+        let action: Action = toml::from_str(
+            r#"
+            bind = "key:j+key:k"
+            combo = true
+            to = "key:esc"
+            "#,
+        )
+        .unwrap();
+
+        let mut inputs = action.combo_inputs();
+        inputs.sort_by_key(|input| input.to_string());
+
+        assert!(action.combo);
+        assert_eq!(50, action.combo_term);
+        assert_eq!(vec![Input::Key(Key::J), Input::Key(Key::K)], inputs);
+    }
+
+    #[test]
+    fn sequence_from_toml() {
+        let action: Action = toml::from_str(
+            r#"
+            bind = ["key:capslock", "key:g", "key:s"]
+            cmd = "git status"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Input::Key(Key::Capslock),
+                Input::Key(Key::G),
+                Input::Key(Key::S)
+            ],
+            action.bind.steps().collect::<Vec<_>>()
+        );
+        assert_eq!(1000, action.sequence_timeout);
+
+        assert!(toml::from_str::<Action>(
+            r#"
+            bind = ["key:capslock", "abs_axis:x"]
+            cmd = "git status"
+            "#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn guards_from_toml() {
+        let action: Action = toml::from_str(
+            r#"
+            bind = "btn:south"
+            to = "key:enter"
+            while = "abs_axis:z > 200"
+            unless = "key:leftshift"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!("abs_axis:z>200", action.r#while.unwrap().to_string());
+        assert_eq!("key:leftshift", action.unless.unwrap().to_string());
+
+        let values = |z: i32, shift: i32| {
+            move |input| match input {
+                Input::AbsAxis(_) => z,
+                _ => shift,
+            }
+        };
+
+        assert!(action.guards_hold(values(255, 0)));
+        assert!(!action.guards_hold(values(100, 0)));
+        assert!(!action.guards_hold(values(255, 1)));
+
+        assert!(toml::from_str::<Action>(
+            r#"
+            bind = "btn:south"
+            to = "key:enter"
+            while = "rel_axis:wheel > 0"
+            "#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn cross_device_from_toml() {
+        let action: Action = toml::from_str(
+            r#"
+            bind = "key:b"
+            from = "Foot Pedal"
+            layer_momentary = "shift"
+            "#,
+        )
+        .unwrap();
+
+        let (keypad, pedal) = (
+            DeviceNames::unknown("Keypad"),
+            DeviceNames::unknown("Foot Pedal"),
+        );
+
+        assert!(action.listens_to("Keypad", &pedal));
+        assert!(!action.listens_to("Keypad", &keypad));
+        assert!(action.depends_on("Keypad", &pedal, Input::Key(Key::B)));
+        assert!(!action.depends_on("Keypad", &keypad, Input::Key(Key::B)));
+
+        let action: Action = toml::from_str(
+            r#"
+            bind = "key:kp1"
+            to = "key:f13"
+            unless = "key:c"
+            guard_device = "Foot Pedal"
+            "#,
+        )
+        .unwrap();
+
+        assert!(action.listens_to("Keypad", &keypad));
+        assert!(action.depends_on("Keypad", &pedal, Input::Key(Key::C)));
+        assert!(!action.depends_on("Keypad", &pedal, Input::Key(Key::Kp1)));
+    }
+
+    #[test]
+    fn cross_device_identical_devices() {
+        let action: Action = toml::from_str(
+            r#"
+            bind = "key:b"
+            to = "key:f13"
+            from = "Foot Pedal"
+            unless = "key:c"
+            guard_device = "Foot Pedal"
+            "#,
+        )
+        .unwrap();
+
+        // The second pedal plugged in runs a copy of the actions of the first
+        let name = || "Foot Pedal".to_string();
+        let first = DeviceNames::new(name(), name(), name());
+        let second = DeviceNames::new("Foot Pedal (usb-2)".to_string(), name(), name());
+
+        for pedal in [&first, &second] {
+            assert!(action.listens_to("Keypad", pedal));
+            assert!(action.guarded_by("Keypad", pedal));
+            assert!(action.depends_on("Keypad", pedal, Input::Key(Key::B)));
+        }
+
+        // Player slots go by the name of the configured device too
+        let player = DeviceNames::new(
+            "Foot Pedal #2".to_string(),
+            "Foot Pedal #2".to_string(),
+            name(),
+        );
+        assert!(action.listens_to("Keypad", &player));
+
+        let action: Action = toml::from_str(
+            r#"
+            bind = "key:b"
+            to = "key:f13"
+            from = "Foot Pedal #2"
+            "#,
+        )
+        .unwrap();
+
+        assert!(action.listens_to("Keypad", &player));
+        assert!(!action.listens_to("Keypad", &first));
+        assert!(!action.listens_to("Keypad", &second));
+    }
+
+    #[test]
+    fn player_from_toml() {
+        let device: Device = toml::from_str(
+            r#"
+            name = "8BitDo Zero 2 gamepad"
+            player = 2
+            output = "Pad 2"
+
+            [[actions]]
+            bind = "btn:south"
+            to = "btn:south"
+
+            [[actions]]
+            bind = "btn:start"
+            to = "key:esc"
+            output = "Keyboard"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!("8BitDo Zero 2 gamepad #2", device.id());
+
+        let actions = device.into_actions();
+
+        assert_eq!(Some("Pad 2"), actions[0].output.as_deref());
+        assert_eq!(Some("Keyboard"), actions[1].output.as_deref());
+    }
+
+    #[test]
+    fn motion_speed() {
+        let motion = Motion {
+            deadzone: 0.5,
+            sensitivity: 1000.0,
+            acceleration: 2.0,
+            tick_rate: 100.0,
+        };
+
+        assert_eq!(0.0, motion.speed(0.1));
+        assert_eq!(0.0, motion.speed(0.5));
+        assert_eq!(250.0, motion.speed(0.75));
+        assert_eq!(1000.0, motion.speed(1.0));
+    }
+
+    #[test]
+    fn zone_contains() {
+        let zone = Zone {
+            min: 0.25,
+            max: 0.5,
+            to: Input::Key(Key::Key2),
+        };
+
+        assert!(zone.contains(0.25, 0.0));
+        assert!(!zone.contains(0.2, 0.0));
+        assert!(zone.contains(0.2, 0.05));
+        assert!(!zone.contains(0.6, 0.05));
+    }
+
+    #[test]
+    fn axis_info_overrides_source() {
+        let overrides = AxisInfo {
+            max: Some(255),
+            flat: Some(0),
+            ..Default::default()
+        };
+
+        let abs_info = overrides.abs_info(Some(AbsInfo::new(0, 0, 1023, 4, 16, 0)));
+
+        assert_eq!(0, abs_info.minimum());
+        assert_eq!(255, abs_info.maximum());
+        assert_eq!(4, abs_info.fuzz());
+        assert_eq!(0, abs_info.flat());
+    }
+}
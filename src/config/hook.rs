@@ -0,0 +1,91 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// A command run by a hook action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    pub cmd: HookCommand,
+    /// Variables added to the environment of the command
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Working directory of the command, that of CoMB if `None`
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// File the output of the command is appended to, discarded if `None`
+    #[serde(default)]
+    pub stdout: Option<PathBuf>,
+    /// File the errors of the command are appended to, those of CoMB if
+    /// `None`
+    #[serde(default)]
+    pub stderr: Option<PathBuf>,
+    /// What running the hook again does while its command is still running
+    #[serde(default)]
+    pub policy: HookPolicy,
+    /// Time in milliseconds after which the command is terminated, and killed
+    /// if it does not exit
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// Whether the command is terminated when the bound input is released
+    #[serde(default)]
+    pub kill_on_release: bool,
+}
+
+/// A command line run by `sh -c`, or a program and its arguments run directly
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HookCommand {
+    Shell(String),
+    Argv(Vec<String>),
+}
+
+/// What running a hook does while its command is still running
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookPolicy {
+    /// Runs the command again alongside the running one
+    #[default]
+    Parallel,
+    /// Does nothing
+    Single,
+    /// Terminates the running command and runs it again
+    Restart,
+    /// Runs the command again once the running one has exited
+    Queue,
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn hook_from_toml() {
+        let hook: Hook = toml::from_str(
+            r#"
+            cmd = ["notify-send", "CoMB", "pressed"]
+            env = { DISPLAY = ":0" }
+            stderr = "/tmp/comb.log"
+            policy = "restart"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            hook.cmd,
+            HookCommand::Argv(vec![
+                "notify-send".to_string(),
+                "CoMB".to_string(),
+                "pressed".to_string()
+            ])
+        );
+        assert_eq!(hook.env.get("DISPLAY").map(String::as_str), Some(":0"));
+        assert_eq!(hook.stderr, Some(PathBuf::from("/tmp/comb.log")));
+        assert_eq!(hook.stdout, None);
+        assert_eq!(hook.policy, HookPolicy::Restart);
+        assert!(!hook.kill_on_release);
+
+        let hook: Hook = toml::from_str(r#"cmd = "swaylock""#).unwrap();
+
+        assert_eq!(hook.cmd, HookCommand::Shell("swaylock".to_string()));
+    }
+}
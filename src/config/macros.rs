@@ -0,0 +1,127 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::input::Input;
+
+use super::util::display_from_str;
+
+/// A single step of a macro, e.g. `key:c`, `key:leftctrl down` or
+/// `sleep 20ms`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MacroStep {
+    /// Presses and releases a key or button
+    Tap(Input),
+    /// Sets an input to a value, `down` and `up` being 1 and 0
+    Set(Input, i32),
+    Sleep(Duration),
+}
+impl MacroStep {
+    pub fn input(&self) -> Option<Input> {
+        match self {
+            Self::Tap(input) | Self::Set(input, _) => Some(*input),
+            Self::Sleep(_) => None,
+        }
+    }
+}
+impl FromStr for MacroStep {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let (step, arg) = match s.split_once(char::is_whitespace) {
+            Some((step, arg)) => (step, Some(arg.trim())),
+            None => (s, None),
+        };
+
+        if step == "sleep" {
+            let arg = arg.ok_or(())?;
+
+            let duration = if let Some(ms) = arg.strip_suffix("ms") {
+                Duration::from_millis(ms.trim().parse().map_err(|_| ())?)
+            } else if let Some(s) = arg.strip_suffix('s') {
+                Duration::from_secs(s.trim().parse().map_err(|_| ())?)
+            } else {
+                Duration::from_millis(arg.parse().map_err(|_| ())?)
+            };
+
+            return Ok(Self::Sleep(duration));
+        }
+
+        let input: Input = step.parse()?;
+
+        match arg {
+            None if input.is_toggle() => Ok(Self::Tap(input)),
+            Some("down") if input.is_toggle() => Ok(Self::Set(input, 1)),
+            Some("up") if input.is_toggle() => Ok(Self::Set(input, 0)),
+            Some(value) => Ok(Self::Set(input, value.parse().map_err(|_| ())?)),
+            None => Err(()),
+        }
+    }
+}
+impl Display for MacroStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tap(input) => write!(f, "{}", input),
+            Self::Set(input, 1) if input.is_toggle() => write!(f, "{} down", input),
+            Self::Set(input, 0) if input.is_toggle() => write!(f, "{} up", input),
+            Self::Set(input, value) => write!(f, "{} {}", input, value),
+            Self::Sleep(duration) => write!(f, "sleep {}ms", duration.as_millis()),
+        }
+    }
+}
+impl<'de> Deserialize<'de> for MacroStep {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        display_from_str::deserialize(deserializer)
+    }
+}
+impl Serialize for MacroStep {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        display_from_str::serialize(self, serializer)
+    }
+}
+
+/// What pressing the binding of a macro does while the macro is playing
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroPolicy {
+    /// Plays the macro again once it has finished
+    #[default]
+    Queue,
+    /// Stops the macro
+    Cancel,
+    /// Stops the macro and plays it again from the start
+    Restart,
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[allow(unused_imports)]
+    use crate::input::{Key, RelAxis};
+
+    #[test]
+    fn macro_step_from_str() {
+        let ctrl = Input::Key(Key::Leftctrl);
+
+        assert_eq!(Ok(MacroStep::Tap(Input::Key(Key::C))), "key:c".parse());
+        assert_eq!(Ok(MacroStep::Set(ctrl, 1)), "key:leftctrl down".parse());
+        assert_eq!(Ok(MacroStep::Set(ctrl, 0)), "key:leftctrl  up".parse());
+        assert_eq!(
+            Ok(MacroStep::Set(Input::RelAxis(RelAxis::X), -10)),
+            "rel_axis:x -10".parse()
+        );
+        assert_eq!(
+            Ok(MacroStep::Sleep(Duration::from_millis(20))),
+            "sleep 20ms".parse()
+        );
+        assert_eq!(
+            Ok(MacroStep::Sleep(Duration::from_secs(1))),
+            "sleep 1s".parse()
+        );
+        assert_eq!(Err(()), "rel_axis:x".parse::<MacroStep>());
+        assert_eq!(Err(()), "sleep".parse::<MacroStep>());
+    }
+}
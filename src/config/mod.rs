@@ -1,5 +1,10 @@
 mod device;
+mod hook;
+mod macros;
 mod monitor;
+mod output;
+mod profile;
+mod transform;
 mod util;
 
 use std::{
@@ -12,13 +17,21 @@ pub use monitor::watch;
 use serde::{Deserialize, Serialize};
 use xdg::BaseDirectoriesError;
 
-pub use self::device::*;
+pub use self::{device::*, hook::*, macros::*, output::*, profile::*, transform::*};
+
+use crate::input::Layout;
 
 #[cfg(feature = "tokio")]
 use tokio::{fs::File, io::AsyncReadExt};
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
+    /// Keyboard layout used by print actions
+    #[serde(default)]
+    pub layout: Layout,
+    /// Virtual devices besides the default one
+    #[serde(default)]
+    pub outputs: Vec<Output>,
     #[serde(default)]
     pub devices: Vec<Device>,
 }
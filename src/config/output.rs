@@ -0,0 +1,107 @@
+use evdev::{BusType, InputId};
+use serde::{Deserialize, Serialize};
+
+use super::Profile;
+
+/// A virtual device bindings can emit through instead of the default one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Output {
+    /// Name of the virtual device, used by bindings to pick it
+    pub name: String,
+    /// Controller whose ids, buttons and axes the virtual device copies
+    #[serde(default)]
+    pub profile: Option<Profile>,
+    #[serde(default)]
+    pub vendor: Option<u16>,
+    #[serde(default)]
+    pub product: Option<u16>,
+    #[serde(default)]
+    pub version: Option<u16>,
+    #[serde(default)]
+    pub bus: Option<Bus>,
+}
+impl Output {
+    /// The ids of the virtual device, those left out being taken from its
+    /// profile
+    pub fn input_id(&self) -> InputId {
+        let profile = self.profile.map(|profile| profile.input_id());
+        let profile = profile.as_ref();
+
+        let bus_type = match (self.bus, profile) {
+            (Some(bus), _) => bus.into(),
+            (None, Some(profile)) => profile.bus_type(),
+            (None, None) => BusType::BUS_USB,
+        };
+
+        InputId::new(
+            bus_type,
+            self.vendor
+                .or(profile.map(|profile| profile.vendor()))
+                .unwrap_or(0x1234),
+            self.product
+                .or(profile.map(|profile| profile.product()))
+                .unwrap_or(0x5678),
+            self.version
+                .or(profile.map(|profile| profile.version()))
+                .unwrap_or(0x111),
+        )
+    }
+}
+
+/// The bus a virtual device claims to be connected through
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bus {
+    Usb,
+    Bluetooth,
+    Virtual,
+    I8042,
+    I2c,
+}
+impl From<Bus> for BusType {
+    fn from(value: Bus) -> Self {
+        match value {
+            Bus::Usb => BusType::BUS_USB,
+            Bus::Bluetooth => BusType::BUS_BLUETOOTH,
+            Bus::Virtual => BusType::BUS_VIRTUAL,
+            Bus::I8042 => BusType::BUS_I8042,
+            Bus::I2c => BusType::BUS_I2C,
+        }
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn output_from_toml() {
+        let output: Output = toml::from_str(
+            r#"
+            name = "CoMB Mouse"
+            vendor = 0x046d
+            bus = "bluetooth"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(output.name, "CoMB Mouse");
+        assert_eq!(output.input_id().vendor(), 0x046d);
+        assert_eq!(output.input_id().product(), 0x5678);
+        assert_eq!(output.input_id().bus_type().0, BusType::BUS_BLUETOOTH.0);
+
+        let output: Output = toml::from_str(
+            r#"
+            name = "CoMB Pad"
+            profile = "xbox360"
+            version = 0x0110
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(output.input_id().vendor(), 0x045e);
+        assert_eq!(output.input_id().product(), 0x028e);
+        assert_eq!(output.input_id().version(), 0x0110);
+        assert_eq!(output.input_id().bus_type().0, BusType::BUS_USB.0);
+    }
+}
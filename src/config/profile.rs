@@ -0,0 +1,94 @@
+use evdev::{AbsInfo, BusType, InputId};
+use serde::{Deserialize, Serialize};
+
+use crate::input::{AbsAxis, Btn};
+
+/// A well-known controller an output can imitate, so that games and SDL
+/// treat it as a standard gamepad
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    /// Wired Xbox 360 controller, as set up by the xpad driver
+    Xbox360,
+    /// DualShock 4 (second revision), as set up by the hid-playstation driver
+    Dualshock4,
+}
+impl Profile {
+    pub fn input_id(&self) -> InputId {
+        match self {
+            Self::Xbox360 => InputId::new(BusType::BUS_USB, 0x045e, 0x028e, 0x0114),
+            Self::Dualshock4 => InputId::new(BusType::BUS_USB, 0x054c, 0x09cc, 0x8111),
+        }
+    }
+
+    pub fn buttons(&self) -> &'static [Btn] {
+        match self {
+            Self::Xbox360 => &[
+                Btn::South,
+                Btn::East,
+                Btn::North,
+                Btn::West,
+                Btn::Tl,
+                Btn::Tr,
+                Btn::Select,
+                Btn::Start,
+                Btn::Mode,
+                Btn::Thumbl,
+                Btn::Thumbr,
+            ],
+            Self::Dualshock4 => &[
+                Btn::South,
+                Btn::East,
+                Btn::North,
+                Btn::West,
+                Btn::Tl,
+                Btn::Tr,
+                Btn::Tl2,
+                Btn::Tr2,
+                Btn::Select,
+                Btn::Start,
+                Btn::Mode,
+                Btn::Thumbl,
+                Btn::Thumbr,
+            ],
+        }
+    }
+
+    pub fn axes(&self) -> Vec<(AbsAxis, AbsInfo)> {
+        let hat = AbsInfo::new(0, -1, 1, 0, 0, 0);
+
+        match self {
+            Self::Xbox360 => {
+                let stick = AbsInfo::new(0, -32768, 32767, 16, 128, 0);
+                let trigger = AbsInfo::new(0, 0, 255, 0, 0, 0);
+
+                vec![
+                    (AbsAxis::X, stick),
+                    (AbsAxis::Y, stick),
+                    (AbsAxis::RX, stick),
+                    (AbsAxis::RY, stick),
+                    (AbsAxis::Z, trigger),
+                    (AbsAxis::RZ, trigger),
+                    (AbsAxis::Hat0X, hat),
+                    (AbsAxis::Hat0Y, hat),
+                ]
+            }
+            Self::Dualshock4 => {
+                // Sticks rest at the middle of their range
+                let stick = AbsInfo::new(128, 0, 255, 0, 0, 0);
+                let trigger = AbsInfo::new(0, 0, 255, 0, 0, 0);
+
+                vec![
+                    (AbsAxis::X, stick),
+                    (AbsAxis::Y, stick),
+                    (AbsAxis::RX, stick),
+                    (AbsAxis::RY, stick),
+                    (AbsAxis::Z, trigger),
+                    (AbsAxis::RZ, trigger),
+                    (AbsAxis::Hat0X, hat),
+                    (AbsAxis::Hat0Y, hat),
+                ]
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::{fmt::Display, str::FromStr};
+
+use evdev::AbsInfo;
+use serde::{Deserialize, Serialize};
+
+use crate::input::AbsAxis;
+
+/// Changes applied to the value of an axis bound to another axis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transform {
+    #[serde(default)]
+    pub invert: bool,
+    #[serde(default = "Transform::scale")]
+    pub scale: f64,
+    /// Added to the value after it has been scaled, in units of the target
+    #[serde(default)]
+    pub offset: f64,
+    /// Lowest and highest value emitted, in units of the target
+    #[serde(default)]
+    pub clamp: Option<[i32; 2]>,
+    /// Response curve of absolute axes
+    #[serde(default)]
+    pub curve: Curve,
+    /// Whether the source axis rests at the middle of its range rather than
+    /// at its minimum, following the type of the axis if `None`
+    #[serde(default)]
+    pub centred: Option<bool>,
+}
+impl Transform {
+    pub fn scale() -> f64 {
+        1.0
+    }
+
+    pub fn is_identity(&self) -> bool {
+        !self.invert
+            && self.scale == 1.0
+            && self.offset == 0.0
+            && self.clamp.is_none()
+            && self.curve == Curve::Linear
+    }
+
+    /// Whether an absolute axis is deflected from the middle of its range
+    pub fn is_centred(&self, axis: AbsAxis) -> bool {
+        self.centred.unwrap_or_else(|| axis.is_centred())
+    }
+
+    /// Transforms the value of an absolute axis into the range of another.
+    /// Centred axes are deflected from the middle of their range, others from
+    /// their minimum.
+    pub fn apply_abs(&self, value: i32, centred: bool, source: &AbsInfo, target: &AbsInfo) -> i32 {
+        let (minimum, maximum) = (source.minimum() as f64, source.maximum() as f64);
+
+        if minimum >= maximum {
+            return value;
+        }
+
+        let mut x = if centred {
+            (value as f64 - (minimum + maximum) / 2.0) / ((maximum - minimum) / 2.0)
+        } else {
+            (value as f64 - minimum) / (maximum - minimum)
+        };
+
+        if self.invert {
+            x = if centred { -x } else { 1.0 - x };
+        }
+
+        x = x.signum() * self.curve.apply(x.abs()) * self.scale;
+        x = if centred {
+            x.clamp(-1.0, 1.0)
+        } else {
+            x.clamp(0.0, 1.0)
+        };
+
+        let (minimum, maximum) = (target.minimum() as f64, target.maximum() as f64);
+
+        let value = if centred {
+            (minimum + maximum) / 2.0 + x * (maximum - minimum) / 2.0
+        } else {
+            minimum + x * (maximum - minimum)
+        };
+
+        self.clamp((value + self.offset).round() as i32)
+    }
+
+    /// Transforms a relative motion, the curve not applying as relative axes
+    /// have no range
+    pub fn apply_rel(&self, value: f64) -> f64 {
+        let value = if self.invert { -value } else { value };
+
+        value * self.scale + self.offset
+    }
+
+    pub fn clamp(&self, value: i32) -> i32 {
+        match self.clamp {
+            Some([min, max]) => value.clamp(min, max.max(min)),
+            None => value,
+        }
+    }
+}
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            invert: false,
+            scale: Self::scale(),
+            offset: 0.0,
+            clamp: None,
+            curve: Curve::default(),
+            centred: None,
+        }
+    }
+}
+
+/// Maps the deflection of an axis, between 0 and 1, to a new deflection
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(try_from = "CurveRepr", into = "CurveRepr")]
+pub enum Curve {
+    #[default]
+    Linear,
+    /// The deflection raised to the given power, e.g. `exponential:2`
+    Exponential(f64),
+    /// Straight lines between `[deflection, output]` points, sorted by
+    /// deflection
+    Piecewise(Vec<[f64; 2]>),
+}
+impl Curve {
+    pub fn apply(&self, x: f64) -> f64 {
+        match self {
+            Self::Linear => x,
+            Self::Exponential(exponent) => x.powf(*exponent),
+            Self::Piecewise(points) => {
+                let Some(end) = points.iter().position(|point| point[0] >= x) else {
+                    return points.last().map_or(x, |point| point[1]);
+                };
+
+                let [x1, y1] = points[end];
+
+                let Some(&[x0, y0]) = end.checked_sub(1).map(|start| &points[start]) else {
+                    return y1;
+                };
+
+                if x1 == x0 {
+                    y1
+                } else {
+                    y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+                }
+            }
+        }
+    }
+}
+impl FromStr for Curve {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "linear" => Ok(Self::Linear),
+            "exponential" => Ok(Self::Exponential(2.0)),
+            s => {
+                let exponent = s.strip_prefix("exponential:").ok_or(())?;
+
+                Ok(Self::Exponential(exponent.trim().parse().map_err(|_| ())?))
+            }
+        }
+    }
+}
+impl Display for Curve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Linear => write!(f, "linear"),
+            Self::Exponential(exponent) => write!(f, "exponential:{}", exponent),
+            Self::Piecewise(points) => write!(f, "{:?}", points),
+        }
+    }
+}
+
+/// A curve is either named by a string or given as a list of points
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CurveRepr {
+    Named(String),
+    Points(Vec<[f64; 2]>),
+}
+impl TryFrom<CurveRepr> for Curve {
+    type Error = String;
+
+    fn try_from(repr: CurveRepr) -> Result<Self, Self::Error> {
+        match repr {
+            CurveRepr::Named(name) => name
+                .parse()
+                .map_err(|_| format!("Unknown curve {:?}", name)),
+            CurveRepr::Points(mut points) => {
+                points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+
+                Ok(Self::Piecewise(points))
+            }
+        }
+    }
+}
+impl From<Curve> for CurveRepr {
+    fn from(curve: Curve) -> Self {
+        match curve {
+            Curve::Piecewise(points) => Self::Points(points),
+            curve => Self::Named(curve.to_string()),
+        }
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn curve_apply() {
+        let curve = Curve::Piecewise(vec![[0.0, 0.0], [0.5, 0.25], [1.0, 1.0]]);
+
+        assert_eq!(0.125, curve.apply(0.25));
+        assert_eq!(0.625, curve.apply(0.75));
+        assert_eq!(0.25, Curve::Exponential(2.0).apply(0.5));
+        assert_eq!(Ok(Curve::Exponential(3.0)), "exponential:3".parse());
+    }
+
+    #[test]
+    fn transform_apply_abs() {
+        let stick = AbsInfo::new(0, -32768, 32767, 0, 0, 0);
+        let trigger = AbsInfo::new(0, 0, 255, 0, 0, 0);
+
+        let invert = Transform {
+            invert: true,
+            ..Default::default()
+        };
+
+        assert_eq!(255, invert.apply_abs(0, false, &trigger, &trigger));
+        assert_eq!(32767, invert.apply_abs(-32768, true, &stick, &stick));
+
+        let half = Transform {
+            scale: 0.5,
+            ..Default::default()
+        };
+
+        assert_eq!(128, half.apply_abs(255, false, &trigger, &trigger));
+
+        let clamped = Transform {
+            clamp: Some([0, 100]),
+            ..half.clone()
+        };
+
+        assert_eq!(
+            100,
+            clamped.apply_abs(255, false, &trigger, &AbsInfo::new(0, 0, 1023, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn transform_apply_abs_centred() {
+        // Sticks of most HID gamepads rest at 128 of 0..255
+        let stick = AbsInfo::new(128, 0, 255, 0, 0, 0);
+        let xbox = AbsInfo::new(0, -32768, 32767, 0, 0, 0);
+
+        let quadratic = Transform {
+            curve: Curve::Exponential(2.0),
+            ..Default::default()
+        };
+
+        assert!(quadratic.is_centred(AbsAxis::X));
+        assert!(!quadratic.is_centred(AbsAxis::Z));
+
+        let centred = quadratic.is_centred(AbsAxis::X);
+
+        assert_eq!(128, quadratic.apply_abs(128, centred, &stick, &stick));
+        assert_eq!(255, quadratic.apply_abs(255, centred, &stick, &stick));
+        assert_eq!(0, quadratic.apply_abs(0, centred, &stick, &stick));
+        assert_eq!(0, quadratic.apply_abs(128, centred, &stick, &xbox));
+
+        let invert = Transform {
+            invert: true,
+            ..Default::default()
+        };
+
+        assert_eq!(127, invert.apply_abs(128, centred, &stick, &stick));
+        assert_eq!(0, invert.apply_abs(255, centred, &stick, &stick));
+
+        let trigger = Transform {
+            centred: Some(false),
+            ..quadratic
+        };
+
+        assert!(!trigger.is_centred(AbsAxis::X));
+    }
+}
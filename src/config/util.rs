@@ -41,7 +41,6 @@ pub(super) mod display_from_str {
     }
 }
 
-#[allow(unused)]
 pub(super) mod display_from_str_option {
     use std::{fmt::Display, marker::PhantomData, str::FromStr};
 
@@ -99,3 +98,59 @@ pub(super) mod display_from_str_option {
         }
     }
 }
+
+/// A chord, or a sequence of inputs given as a list
+pub(super) mod chord_or_sequence {
+    use serde::{ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+
+    use crate::input::{Chord, Input};
+
+    use super::display_from_str;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Bind {
+        Chord(#[serde(with = "display_from_str")] Chord),
+        Sequence(Vec<String>),
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Chord, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let steps = match Bind::deserialize(deserializer)? {
+            Bind::Chord(chord) => return Ok(chord),
+            Bind::Sequence(steps) => steps,
+        };
+
+        let steps = steps
+            .iter()
+            .map(|step| step.trim().parse())
+            .collect::<Result<Vec<Input>, ()>>()
+            .map_err(|_| serde::de::Error::custom("Failed to parse sequence"))?;
+
+        // Only inputs that are pressed and released can be typed in order
+        if !steps.iter().all(Input::is_toggle) {
+            return Err(serde::de::Error::custom(
+                "Sequences can only contain keys and buttons",
+            ));
+        }
+
+        Chord::sequence(steps).ok_or_else(|| serde::de::Error::custom("Sequence is empty"))
+    }
+
+    pub fn serialize<S>(chord: &Chord, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if chord.sequence.is_empty() {
+            return display_from_str::serialize(chord, serializer);
+        }
+
+        let mut seq = serializer.serialize_seq(Some(chord.sequence.len() + 1))?;
+        for step in chord.steps() {
+            seq.serialize_element(&step.to_string())?;
+        }
+        seq.end()
+    }
+}
@@ -3,7 +3,7 @@ use std::{io, time::SystemTime};
 use crate::events::{Event, EventPipelineSender};
 #[cfg(feature = "tokio")]
 use evdev::EventStream;
-use evdev::{InputEvent, InputEventKind, MiscType};
+use evdev::{uinput::VirtualDevice, InputEvent, InputEventKind};
 
 #[cfg(not(feature = "tokio"))]
 use mio::{unix::SourceFd, Events, Interest, Poll, Token};
@@ -25,7 +25,7 @@ use std::thread::JoinHandle;
 #[cfg(feature = "tokio")]
 use tokio_stream::StreamMap;
 
-use super::{DeviceId, DeviceIdCombo};
+use super::{abs_info, passthrough_device, DeviceId, DeviceIdCombo};
 
 #[derive(Debug, Clone)]
 pub struct DeviceEvent {
@@ -33,6 +33,7 @@ pub struct DeviceEvent {
     timestamp: SystemTime,
     kind: InputEventKind,
     value: i32,
+    raw: InputEvent,
 }
 impl DeviceEvent {
     fn new(device: String, event: InputEvent) -> Self {
@@ -41,6 +42,7 @@ impl DeviceEvent {
             timestamp: event.timestamp(),
             kind: event.kind(),
             value: event.value(),
+            raw: event,
         }
     }
 
@@ -59,11 +61,16 @@ impl DeviceEvent {
     pub fn kind(&self) -> InputEventKind {
         self.kind
     }
+
+    /// The event as read from the device
+    pub fn raw(&self) -> InputEvent {
+        self.raw
+    }
 }
 
 #[derive(Debug)]
 enum DeviceUpdate {
-    Add(DeviceIdCombo),
+    Add(Box<DeviceIdCombo>),
     Remove(DeviceId),
 }
 
@@ -103,7 +110,7 @@ impl DeviceEventWatch {
         for device in devices {
             let _ = self
                 .device_update_channel
-                .send(DeviceUpdate::Add(device))
+                .send(DeviceUpdate::Add(Box::new(device)))
                 .await;
         }
     }
@@ -111,11 +118,27 @@ impl DeviceEventWatch {
     #[cfg(not(feature = "tokio"))]
     pub fn watch(&self, devices: Vec<DeviceIdCombo>) {
         for device in devices {
-            let _ = self.device_update_channel.send(DeviceUpdate::Add(device));
+            let _ = self
+                .device_update_channel
+                .send(DeviceUpdate::Add(Box::new(device)));
         }
     }
 
-    #[allow(unused)]
+    /// Stops watching the devices, releasing their grabs before any device
+    /// watched afterwards is grabbed
+    #[cfg(feature = "tokio")]
+    pub async fn unwatch(&self, devices: Vec<DeviceId>) {
+        for device in devices {
+            let _ = self
+                .device_update_channel
+                .send(DeviceUpdate::Remove(device))
+                .await;
+        }
+    }
+
+    /// Stops watching the devices, releasing their grabs before any device
+    /// watched afterwards is grabbed
+    #[cfg(not(feature = "tokio"))]
     pub fn unwatch(&self, devices: Vec<DeviceId>) {
         for device in devices {
             let _ = self
@@ -129,8 +152,9 @@ impl DeviceEventWatch {
 const UPDATE_CHANNEL: Token = Token(0);
 
 struct DeviceEventWatcher {
+    /// The event streams by device id and instance
     #[cfg(feature = "tokio")]
-    event_stream_map: StreamMap<DeviceId, EventStream>,
+    event_stream_map: StreamMap<(DeviceId, String), EventStream>,
     #[cfg(not(feature = "tokio"))]
     poll: Poll,
     #[cfg(not(feature = "tokio"))]
@@ -182,7 +206,9 @@ impl DeviceEventWatcher {
 
         loop {
             tokio::select! {
-                Some((id, event)) = self.event_stream_map.next() => self.handle_event(id, event),
+                Some(((id, instance), event)) = self.event_stream_map.next() => {
+                    self.handle_event(id, instance, event)
+                }
                 Some(update) = self.device_update_channel.recv() => self.update_devices(update),
             }
         }
@@ -212,17 +238,26 @@ impl DeviceEventWatcher {
 
     fn update_devices(&mut self, update: DeviceUpdate) {
         match update {
-            DeviceUpdate::Add(device) => self.add_device(device),
-            DeviceUpdate::Remove(device) => self.remove_device(device),
+            DeviceUpdate::Add(device) => self.add_device(*device),
+            DeviceUpdate::Remove(device) => self.remove_devices(device),
         }
     }
 
-    fn add_device(&mut self, device: DeviceIdCombo) {
+    fn add_device(&mut self, mut device: DeviceIdCombo) {
         let name = device
             .name()
             .or(device.unique_name())
             .map(|name| name.to_owned());
         let id = device.id().to_owned();
+        let section = device.section().to_owned();
+        let instance = device.instance().to_owned();
+        let abs_info = abs_info(&device);
+
+        let passthrough = if device.grab_requested() {
+            Self::grab(&mut device)
+        } else {
+            None
+        };
 
         #[cfg(not(feature = "tokio"))]
         {
@@ -248,27 +283,87 @@ impl DeviceEventWatcher {
 
         #[cfg(feature = "tokio")]
         self.event_stream_map.insert(
-            device.id().to_string(),
+            (id.clone(), instance.clone()),
             device.device.into_event_stream().unwrap(),
         );
 
         if let Some(name) = name {
-            println!("Added {} ({})", name, id);
+            println!("Added {} ({}, {})", name, id, instance);
         } else {
-            println!("Added {}", id);
+            println!("Added {} ({})", id, instance);
+        }
+
+        let _ = self.event_pipeline.send(Event::DeviceAdded {
+            id,
+            section,
+            instance,
+            abs_info,
+            passthrough,
+        });
+    }
+
+    /// Grabs a device so that its events only reach CoMB, returning the
+    /// virtual device its unused events are passed on through
+    fn grab(device: &mut DeviceIdCombo) -> Option<VirtualDevice> {
+        let passthrough = match passthrough_device(device) {
+            Ok(passthrough) => passthrough,
+            Err(err) => {
+                println!(
+                    "Failed to create passthrough device for {}: {}",
+                    device.id(),
+                    err
+                );
+                return None;
+            }
+        };
+
+        if let Err(err) = device.grab() {
+            println!("Failed to grab {}: {}", device.id(), err);
+            return None;
+        }
+
+        Some(passthrough)
+    }
+
+    /// Stops watching every device with the id
+    fn remove_devices(&mut self, id: DeviceId) {
+        #[cfg(not(feature = "tokio"))]
+        let instances: Vec<String> = self
+            .devices
+            .iter()
+            .filter(|dev| dev.id() == id)
+            .map(|dev| dev.instance().to_string())
+            .collect();
+
+        #[cfg(feature = "tokio")]
+        let instances: Vec<String> = self
+            .event_stream_map
+            .keys()
+            .filter(|(dev, _)| *dev == id)
+            .map(|(_, instance)| instance.clone())
+            .collect();
+
+        for instance in instances {
+            self.remove_device(id.clone(), instance);
         }
     }
 
-    fn remove_device(&mut self, id: DeviceId) {
+    fn remove_device(&mut self, id: DeviceId, instance: String) {
         #[cfg(not(feature = "tokio"))]
         let device = {
-            let Some(idx) = self.devices.iter().position(|dev| {
-                dev.id() == id
-            }) else {
+            let Some(idx) = self
+                .devices
+                .iter()
+                .position(|dev| dev.id() == id && dev.instance() == instance)
+            else {
                 return;
             };
 
-            let device = self.devices.swap_remove(idx);
+            let mut device = self.devices.swap_remove(idx);
+
+            // The grab is also released by the kernel when the device is
+            // closed, which covers shutting down
+            let _ = device.ungrab();
 
             let raw_fd = device.as_raw_fd();
 
@@ -278,30 +373,37 @@ impl DeviceEventWatcher {
         };
 
         #[cfg(feature = "tokio")]
-        let Some(device) = self.event_stream_map.remove(&id) else {
+        let Some(mut device) = self
+            .event_stream_map
+            .remove(&(id.clone(), instance.clone()))
+        else {
             return;
         };
 
+        // The grab is also released by the kernel when the device is closed,
+        // which covers shutting down
+        #[cfg(feature = "tokio")]
+        let _ = device.device_mut().ungrab();
+
         #[cfg(feature = "tokio")]
         let device = device.device();
 
         if let Some(name) = device.name().or(device.unique_name()) {
-            println!("Removed {} ({})", name, id);
+            println!("Removed {} ({}, {})", name, id, instance);
         } else {
-            println!("Removed {}", id);
+            println!("Removed {} ({})", id, instance);
         }
+
+        let _ = self
+            .event_pipeline
+            .send(Event::DeviceRemoved { id, instance });
     }
 
     fn send_event(event_pipeline: &EventPipelineSender, event: InputEvent, id: &str) {
         let event = DeviceEvent::new(id.to_string(), event);
 
-        use evdev::InputEventKind::*;
-
-        match event.kind() {
-            Synchronization(_) | Misc(MiscType::MSC_SCAN) => return,
-            _ => {}
-        }
-
+        // Every event is sent on, including synchronization, so that the
+        // events of grabbed devices can be passed on in frames
         let _ = event_pipeline.send(Event::DeviceEvent(event.clone()));
 
         if let Ok(input) = event.try_into() {
@@ -310,11 +412,16 @@ impl DeviceEventWatcher {
     }
 
     #[cfg(feature = "tokio")]
-    fn handle_event(&mut self, id: DeviceId, event: Result<InputEvent, io::Error>) {
+    fn handle_event(
+        &mut self,
+        id: DeviceId,
+        instance: String,
+        event: Result<InputEvent, io::Error>,
+    ) {
         if let Ok(event) = event {
             Self::send_event(&self.event_pipeline, event, &id);
         } else {
-            self.remove_device(id);
+            self.remove_device(id, instance);
         }
     }
 
@@ -325,6 +432,7 @@ impl DeviceEventWatcher {
             };
 
         let id = device.id().to_string();
+        let instance = device.instance().to_string();
 
         {
             let events = device.fetch_events();
@@ -342,6 +450,49 @@ impl DeviceEventWatcher {
             }
         }
 
-        self.remove_device(id);
+        self.remove_device(id, instance);
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn unwatch_is_sent_before_watch() {
+        let (tx, mut rx) = channel(1);
+
+        let watch = DeviceEventWatch {
+            thread_handle: tokio::spawn(async {}),
+            device_update_channel: tx,
+        };
+
+        // A reload grabs the devices again right after unwatching them
+        watch.unwatch(vec!["Gamepad".to_string()]).await;
+
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(DeviceUpdate::Remove(id)) if id == "Gamepad"
+        ));
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn unwatch_is_sent_before_watch() {
+        let (tx, rx) = channel();
+
+        let watch = DeviceEventWatch {
+            thread_handle: std::thread::spawn(|| {}),
+            device_update_channel: tx,
+        };
+
+        // A reload grabs the devices again right after unwatching them
+        watch.unwatch(vec!["Gamepad".to_string()]);
+
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(DeviceUpdate::Remove(id)) if id == "Gamepad"
+        ));
     }
 }
@@ -2,18 +2,23 @@ pub mod events;
 mod monitor;
 
 use std::{
+    collections::{HashMap, HashSet},
     fmt::Debug,
+    io,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
     time::SystemTime,
 };
 
-use evdev::{Device, InputEventKind};
+use evdev::{
+    uinput::{VirtualDevice, VirtualDeviceBuilder},
+    AbsInfo, Device, InputEventKind, UinputAbsSetup,
+};
 pub use events::DeviceEvent;
 pub use monitor::watch;
 use serde::{Deserialize, Serialize};
 
-use crate::input::InputEvent;
+use crate::input::{AbsAxis, Input, InputEvent, InputState};
 
 pub type DeviceId = String;
 
@@ -22,6 +27,12 @@ pub type DeviceId = String;
 pub enum DeviceAccessor {
     Name(String),
     Path(PathBuf),
+    /// The physical location of the device, e.g. the USB port it is plugged
+    /// into
+    Phys(String),
+    /// The unique identifier of the device, e.g. its serial number or
+    /// Bluetooth address
+    Uniq(String),
 }
 impl DeviceAccessor {
     pub fn canonicalized(&self) -> Self {
@@ -34,35 +45,140 @@ impl DeviceAccessor {
             _ => self.clone()
         }
     }
+
+    pub fn matches(&self, path: &Path, properties: &DeviceProperties) -> bool {
+        match self {
+            Self::Name(name) => properties.name.as_deref() == Some(name.trim()),
+            Self::Path(accessor) => {
+                accessor == path || path.canonicalize().is_ok_and(|path| &path == accessor)
+            }
+            Self::Phys(phys) => properties.phys.as_ref() == Some(phys),
+            Self::Uniq(uniq) => properties.uniq.as_ref() == Some(uniq),
+        }
+    }
 }
 impl ToString for DeviceAccessor {
     fn to_string(&self) -> String {
         match self {
             Self::Name(name) => name.clone(),
             Self::Path(path) => path.to_string_lossy().to_string(),
+            Self::Phys(phys) => phys.clone(),
+            Self::Uniq(uniq) => uniq.clone(),
         }
     }
 }
 
+/// The properties of a device that configured devices are matched by
+#[derive(Debug, Clone, Default)]
+pub struct DeviceProperties {
+    pub name: Option<String>,
+    pub phys: Option<String>,
+    pub uniq: Option<String>,
+}
+impl DeviceProperties {
+    pub fn of(device: &Device) -> Self {
+        Self {
+            name: device.name().map(str::to_string),
+            phys: device.physical_path().map(str::to_string),
+            uniq: device.unique_name().map(str::to_string),
+        }
+    }
+}
+
+/// A configured device, whose player slot tells apart the devices matched by
+/// the same accessor
+#[derive(Debug, Clone)]
+pub struct DeviceSlot {
+    pub accessor: DeviceAccessor,
+    pub player: Option<u8>,
+}
+impl DeviceSlot {
+    /// The id the actions of the configured device are found by
+    pub fn id(&self) -> DeviceId {
+        match self.player {
+            Some(player) => format!("{} #{}", self.accessor.to_string(), player),
+            None => self.accessor.to_string(),
+        }
+    }
+}
+
+/// The names a watched device goes by in the config: its id, the id of the
+/// configured device it matches and the accessor of that device, shared by
+/// every player slot and copy of it
+#[derive(Debug, Clone)]
+pub struct DeviceNames {
+    id: DeviceId,
+    section: DeviceId,
+    accessor: String,
+}
+impl DeviceNames {
+    pub fn new(id: DeviceId, section: DeviceId, accessor: String) -> Self {
+        Self {
+            id,
+            section,
+            accessor,
+        }
+    }
+
+    /// The names of a device that is not watched, only going by its id
+    pub fn unknown(id: &str) -> Self {
+        Self::new(id.to_string(), id.to_string(), id.to_string())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether a device named in the config, e.g. by `from`, is the device
+    pub fn matches(&self, name: &str) -> bool {
+        [&self.id, &self.section, &self.accessor]
+            .iter()
+            .any(|own| *own == name)
+    }
+}
+
 pub struct DeviceIdCombo {
     device: Device,
     id: DeviceId,
+    /// The id of the configured device, which differs from `id` for the
+    /// devices it matches besides the first one without a player slot
+    section: DeviceId,
+    /// The device node, telling apart devices with the same id
+    instance: String,
+    grab: bool,
 }
 impl DeviceIdCombo {
-    pub fn new(id: DeviceId, device: Device) -> Self {
-        Self { device, id }
-    }
-
-    pub fn from_accessor(accessor: DeviceAccessor, device: Device) -> Self {
+    pub fn new(id: DeviceId, section: DeviceId, path: &Path, device: Device) -> Self {
         Self {
             device,
-            id: accessor.to_string(),
+            id,
+            section,
+            instance: path.to_string_lossy().to_string(),
+            grab: false,
         }
     }
 
+    pub fn with_grab(mut self, grab: bool) -> Self {
+        self.grab = grab;
+        self
+    }
+
     pub fn id(&self) -> &str {
         &self.id
     }
+
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    /// Whether the device should be grabbed once watched
+    pub fn grab_requested(&self) -> bool {
+        self.grab
+    }
 }
 impl Deref for DeviceIdCombo {
     type Target = Device;
@@ -78,7 +194,7 @@ impl DerefMut for DeviceIdCombo {
 }
 impl Debug for DeviceIdCombo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "DeviceIdCombo({})", self.id)
+        write!(f, "DeviceIdCombo({}, {})", self.id, self.instance)
     }
 }
 
@@ -88,6 +204,9 @@ pub struct DeviceInput {
     timestamp: SystemTime,
     input_event: InputEvent,
     device: DeviceId,
+    /// The device the event came from, which differs from `device` once the
+    /// event is handled by the actions of another device
+    source: DeviceId,
 }
 impl DeviceInput {
     pub fn input_event(&self) -> InputEvent {
@@ -97,6 +216,39 @@ impl DeviceInput {
     pub fn device(&self) -> &str {
         &self.device
     }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The release of a key or button of a source device that is no longer
+    /// watched, handled by the actions of a device reacting to it
+    pub fn released(device: DeviceId, source: &str, input: Input) -> Option<Self> {
+        let input_event = match input {
+            Input::Key(key) => InputEvent::Key(key, InputState::Released),
+            Input::Btn(btn) => InputEvent::Btn(btn, InputState::Released),
+            _ => return None,
+        };
+
+        Some(Self {
+            timestamp: SystemTime::now(),
+            input_event,
+            device,
+            source: source.to_string(),
+        })
+    }
+
+    /// The event, handled by the actions of another device
+    pub fn routed_to(&self, device: DeviceId) -> Self {
+        Self {
+            device,
+            ..self.clone()
+        }
+    }
+
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
 }
 impl TryFrom<DeviceEvent> for DeviceInput {
     type Error = ();
@@ -119,75 +271,234 @@ impl TryFrom<DeviceEvent> for DeviceInput {
             timestamp: event.timestamp(),
             input_event,
             device: event.device().to_string(),
+            source: event.device().to_string(),
         })
     }
 }
 
-pub fn open_devices(devices: &[DeviceAccessor]) -> Vec<DeviceIdCombo> {
-    let mut opened_devices = vec![];
+/// The range and current value of each absolute axis of a device
+pub fn abs_info(device: &Device) -> HashMap<AbsAxis, AbsInfo> {
+    let Ok(abs_state) = device.get_abs_state() else {
+        return HashMap::new();
+    };
 
-    let names = devices
-        .iter()
-        .filter_map(|dev| match dev {
-            DeviceAccessor::Name(ref name) => Some(name.trim()),
-            _ => None,
-        })
-        .collect::<Vec<&str>>();
+    device
+        .supported_absolute_axes()
+        .into_iter()
+        .flat_map(|axes| axes.iter())
+        .filter_map(|axis| {
+            let info = abs_state.get(axis.0 as usize)?;
 
-    evdev::enumerate()
-        .filter_map(|(_, device)| {
-            device_name_matches(&device, |name| names.contains(&name))
-                .map(|name| name.to_string())
-                .map(|name| DeviceIdCombo::new(name, device))
+            Some((
+                axis.try_into().ok()?,
+                AbsInfo::new(
+                    info.value,
+                    info.minimum,
+                    info.maximum,
+                    info.fuzz,
+                    info.flat,
+                    info.resolution,
+                ),
+            ))
         })
-        // Iterator::collect_into is unstable
-        // (issue #94780 <https://github.com/rust-lang/rust/issues/94780>)
-        .collect_into(&mut opened_devices);
+        .collect()
+}
 
-    devices
+/// Creates a virtual device able to emit the key, button and axis events of a
+/// device, through which the events of a grabbed device are passed on
+pub fn passthrough_device(device: &Device) -> io::Result<VirtualDevice> {
+    let name = format!("CoMB Passthrough {}", device.name().unwrap_or("Device"));
+
+    let mut builder = VirtualDeviceBuilder::new()?.name(&name);
+
+    if let Some(keys) = device.supported_keys() {
+        builder = builder.with_keys(keys)?;
+    }
+
+    if let Some(axes) = device.supported_relative_axes() {
+        builder = builder.with_relative_axes(axes)?;
+    }
+
+    for (axis, abs_info) in abs_info(device) {
+        builder = builder.with_absolute_axis(&UinputAbsSetup::new(axis.into(), abs_info))?;
+    }
+
+    builder.build()
+}
+
+/// Opens every device matched by a configured device
+pub fn open_devices(slots: &[DeviceSlot]) -> Vec<DeviceIdCombo> {
+    assign_devices(evdev::enumerate().collect(), slots, &HashSet::new())
+}
+
+/// Assigns devices to the configured devices matching them. The ids in
+/// `taken` belong to devices that are already watched.
+pub fn assign_devices(
+    devices: Vec<(PathBuf, Device)>,
+    slots: &[DeviceSlot],
+    taken: &HashSet<DeviceId>,
+) -> Vec<DeviceIdCombo> {
+    let properties: Vec<(PathBuf, DeviceProperties)> = devices
         .iter()
-        .filter_map(|dev| match dev {
-            DeviceAccessor::Path(path) => Some(DeviceIdCombo::from_accessor(
-                dev.clone(),
-                Device::open(path).ok()?,
-            )),
-            _ => None,
-        })
-        // Iterator::collect_into is unstable
-        // (issue #94780 <https://github.com/rust-lang/rust/issues/94780>)
-        .collect_into(&mut opened_devices);
+        .map(|(path, device)| (path.clone(), DeviceProperties::of(device)))
+        .collect();
+
+    let ids = assign_ids(&properties, slots, taken);
+
+    devices
+        .into_iter()
+        .zip(ids)
+        .filter_map(|((path, device), ids)| {
+            let (id, section) = ids?;
 
-    opened_devices
+            Some(DeviceIdCombo::new(id, section, &path, device))
+        })
+        .collect()
 }
 
-fn device_name_matches(device: &Device, mut predicate: impl FnMut(&str) -> bool) -> Option<&str> {
-    // let chains are unstable
-    // (issue #53667 https://github.com/rust-lang/rust/issues/53667)
-    if let Some(name) = device.name() && predicate(name) {
-        Some(name)
-    } else if let Some(name) = device.name() && predicate(name) {
-        Some(name)
-    } else {
-        None
+/// Finds the id and the configured device of each device. The devices are
+/// assigned in order of their physical location, unique identifier and device
+/// node, so that player slots are assigned the same way each time.
+fn assign_ids(
+    devices: &[(PathBuf, DeviceProperties)],
+    slots: &[DeviceSlot],
+    taken: &HashSet<DeviceId>,
+) -> Vec<Option<(DeviceId, DeviceId)>> {
+    let mut order: Vec<usize> = (0..devices.len()).collect();
+    order.sort_by_key(|&idx| {
+        let (path, properties) = &devices[idx];
+
+        (&properties.phys, &properties.uniq, path)
+    });
+
+    let mut taken = taken.clone();
+    let mut ids = vec![None; devices.len()];
+
+    for idx in order {
+        let (path, properties) = &devices[idx];
+
+        let Some(slot) = match_slot(path, properties, slots, &taken) else {
+            continue;
+        };
+
+        let section = slot.id();
+        let id = instance_id(&section, path, properties, &taken);
+        taken.insert(id.clone());
+
+        ids[idx] = Some((id, section));
     }
+
+    ids
 }
 
-pub fn path_in_devices<'a>(
-    path: impl AsRef<Path>,
-    device: &Device,
-    accessors: &'a [DeviceAccessor],
-) -> Option<&'a DeviceAccessor> {
-    for accessor in accessors {
-        match accessor {
-            DeviceAccessor::Path(p) if p == path.as_ref() => return Some(accessor),
-            // if let guards are unstable
-            // (issue #51114 https://github.com/rust-lang/rust/issues/51114)
-            DeviceAccessor::Name(name) if let Some(name) = device_name_matches(&device, |n| n == &name[..]) => {
-                return Some(accessor)
-            }
-            _ => {}
-        }
-    }
+/// The id of a device: that of its configured device, unless another device
+/// already has it, in which case the physical location, unique identifier
+/// or device node of the device tell them apart
+fn instance_id(
+    section: &str,
+    path: &Path,
+    properties: &DeviceProperties,
+    taken: &HashSet<DeviceId>,
+) -> DeviceId {
+    let path = path.to_string_lossy().to_string();
+
+    let tags = [properties.phys.clone(), properties.uniq.clone()]
+        .into_iter()
+        .flatten()
+        .filter(|tag| !tag.is_empty())
+        .chain([path.clone()]);
+
+    std::iter::once(section.to_string())
+        .chain(tags.map(|tag| format!("{} ({})", section, tag)))
+        .find(|id| !taken.contains(id))
+        .unwrap_or_else(|| format!("{} ({})", section, path))
+}
+
+/// Finds the configured device a device belongs to: the lowest free player
+/// slot matching it, or else a configured device without a player slot
+fn match_slot<'a>(
+    path: &Path,
+    properties: &DeviceProperties,
+    slots: &'a [DeviceSlot],
+    taken: &HashSet<DeviceId>,
+) -> Option<&'a DeviceSlot> {
+    let mut matching: Vec<&DeviceSlot> = slots
+        .iter()
+        .filter(|slot| slot.accessor.matches(path, properties))
+        .collect();
+
+    matching.sort_by_key(|slot| (slot.player.is_none(), slot.player));
 
-    None
+    matching
+        .into_iter()
+        .find(|slot| slot.player.is_none() || !taken.contains(&slot.id()))
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn assign_identical_devices() {
+        let controller = |phys: &str| DeviceProperties {
+            name: Some("Wireless Controller".to_string()),
+            phys: Some(phys.to_string()),
+            uniq: None,
+        };
+
+        let devices = vec![
+            (PathBuf::from("/dev/input/event21"), controller("usb-2")),
+            (PathBuf::from("/dev/input/event20"), controller("usb-1")),
+        ];
+
+        let slot = |player| DeviceSlot {
+            accessor: DeviceAccessor::Name("Wireless Controller".to_string()),
+            player,
+        };
+
+        let id = |id: &str, section: &str| Some((id.to_string(), section.to_string()));
+
+        // Without a player slot the first device keeps the id of the
+        // configured device
+        assert_eq!(
+            vec![
+                id("Wireless Controller (usb-2)", "Wireless Controller"),
+                id("Wireless Controller", "Wireless Controller"),
+            ],
+            assign_ids(&devices, &[slot(None)], &HashSet::new())
+        );
+
+        // Connecting another controller while the first is watched
+        assert_eq!(
+            vec![id("Wireless Controller (usb-2)", "Wireless Controller")],
+            assign_ids(
+                &devices[..1],
+                &[slot(None)],
+                &HashSet::from(["Wireless Controller".to_string()])
+            )
+        );
+
+        let players = [slot(Some(1)), slot(Some(2))];
+
+        assert_eq!(
+            vec![
+                id("Wireless Controller #2", "Wireless Controller #2"),
+                id("Wireless Controller #1", "Wireless Controller #1"),
+            ],
+            assign_ids(&devices, &players, &HashSet::new())
+        );
+
+        // Devices beyond the player slots are not watched
+        assert_eq!(
+            vec![None],
+            assign_ids(
+                &devices[..1],
+                &players,
+                &HashSet::from([
+                    "Wireless Controller #1".to_string(),
+                    "Wireless Controller #2".to_string()
+                ])
+            )
+        );
+    }
 }
@@ -1,6 +1,12 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
-use crate::device::{DeviceEvent, DeviceInput};
+use evdev::{uinput::VirtualDevice, AbsInfo};
+
+use crate::{
+    device::{DeviceEvent, DeviceId, DeviceInput},
+    input::AbsAxis,
+    timer::TimerId,
+};
 
 pub use self::pipeline::*;
 
@@ -38,4 +44,23 @@ pub enum Event {
     },
     DeviceEvent(DeviceEvent),
     DeviceInput(DeviceInput),
+    /// A device started being watched
+    DeviceAdded {
+        id: DeviceId,
+        /// The id of the configured device whose actions the device runs
+        section: DeviceId,
+        /// The device node, telling apart devices with the same id
+        instance: String,
+        abs_info: HashMap<AbsAxis, AbsInfo>,
+        /// The virtual device events of a grabbed device are passed on
+        /// through
+        passthrough: Option<VirtualDevice>,
+    },
+    /// A device is no longer watched, either because it was unplugged or
+    /// because it was removed from the config
+    DeviceRemoved {
+        id: DeviceId,
+        instance: String,
+    },
+    Timer(TimerId),
 }
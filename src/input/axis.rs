@@ -1,7 +1,7 @@
 use crate::input_enum;
 
 input_enum! {
-    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
     pub enum RelAxis {
         X => "x" evdev::RelativeAxisType::REL_X,
         Y => "y" evdev::RelativeAxisType::REL_Y,
@@ -20,7 +20,7 @@ input_enum! {
 }
 
 input_enum! {
-    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
     pub enum AbsAxis {
         X => "x" evdev::AbsoluteAxisType::ABS_X,
         Y => "y" evdev::AbsoluteAxisType::ABS_Y,
@@ -66,3 +66,28 @@ input_enum! {
     }
     impl TryFrom<evdev::AbsoluteAxisType>;
 }
+impl AbsAxis {
+    /// Whether the axis rests at the middle of its range, like a stick, rather
+    /// than at its minimum, like a trigger or pedal
+    pub fn is_centred(&self) -> bool {
+        matches!(
+            self,
+            Self::X
+                | Self::Y
+                | Self::RX
+                | Self::RY
+                | Self::Rudder
+                | Self::Wheel
+                | Self::Hat0X
+                | Self::Hat0Y
+                | Self::Hat1X
+                | Self::Hat1Y
+                | Self::Hat2X
+                | Self::Hat2Y
+                | Self::Hat3X
+                | Self::Hat3Y
+                | Self::TiltX
+                | Self::TiltY
+        )
+    }
+}
@@ -1,7 +1,7 @@
 use crate::input_enum;
 
 input_enum! {
-    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
     pub enum Btn {
         Key0 => "0" evdev::Key::BTN_0,
         Key1 => "1" evdev::Key::BTN_1,
@@ -1,7 +1,7 @@
 use crate::input_enum;
 
 input_enum! {
-    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
     pub enum Key {
         Esc => "esc" evdev::Key::KEY_ESC,
         Key1 => "1" evdev::Key::KEY_1,
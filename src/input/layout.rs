@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use super::Key;
+
+/// Keyboard layout used to type text on the virtual device
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    #[default]
+    Us,
+    Uk,
+}
+impl Layout {
+    /// The key typing the character, and whether shift has to be held while
+    /// pressing it
+    pub fn key(&self, c: char) -> Option<(Key, bool)> {
+        if c.is_ascii_alphanumeric() {
+            let key = c.to_ascii_lowercase().to_string().parse().ok()?;
+
+            return Some((key, c.is_ascii_uppercase()));
+        }
+
+        let key = match c {
+            ' ' => (Key::Space, false),
+            '\n' => (Key::Enter, false),
+            '\t' => (Key::Tab, false),
+            '-' => (Key::Minus, false),
+            '_' => (Key::Minus, true),
+            '=' => (Key::Equal, false),
+            '+' => (Key::Equal, true),
+            '[' => (Key::Leftbrace, false),
+            '{' => (Key::Leftbrace, true),
+            ']' => (Key::Rightbrace, false),
+            '}' => (Key::Rightbrace, true),
+            ';' => (Key::Semicolon, false),
+            ':' => (Key::Semicolon, true),
+            ',' => (Key::Comma, false),
+            '<' => (Key::Comma, true),
+            '.' => (Key::Dot, false),
+            '>' => (Key::Dot, true),
+            '/' => (Key::Slash, false),
+            '?' => (Key::Slash, true),
+            '!' => (Key::Key1, true),
+            '$' => (Key::Key4, true),
+            '%' => (Key::Key5, true),
+            '^' => (Key::Key6, true),
+            '&' => (Key::Key7, true),
+            '*' => (Key::Key8, true),
+            '(' => (Key::Key9, true),
+            ')' => (Key::Key0, true),
+            '`' => (Key::Grave, false),
+            c => return self.layout_key(c),
+        };
+
+        Some(key)
+    }
+
+    /// Characters whose keys differ between layouts
+    fn layout_key(&self, c: char) -> Option<(Key, bool)> {
+        let key = match self {
+            Self::Us => match c {
+                '@' => (Key::Key2, true),
+                '#' => (Key::Key3, true),
+                '\'' => (Key::Apostrophe, false),
+                '"' => (Key::Apostrophe, true),
+                '~' => (Key::Grave, true),
+                '\\' => (Key::Backslash, false),
+                '|' => (Key::Backslash, true),
+                _ => return None,
+            },
+            Self::Uk => match c {
+                '"' => (Key::Key2, true),
+                '£' => (Key::Key3, true),
+                '\'' => (Key::Apostrophe, false),
+                '@' => (Key::Apostrophe, true),
+                '¬' => (Key::Grave, true),
+                // The key next to enter on ISO keyboards
+                '#' => (Key::Backslash, false),
+                '~' => (Key::Backslash, true),
+                // The key next to left shift on ISO keyboards
+                '\\' => (Key::Key102Nd, false),
+                '|' => (Key::Key102Nd, true),
+                _ => return None,
+            },
+        };
+
+        Some(key)
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn layout_key() {
+        assert_eq!(Some((Key::A, false)), Layout::Us.key('a'));
+        assert_eq!(Some((Key::A, true)), Layout::Us.key('A'));
+        assert_eq!(Some((Key::Key2, true)), Layout::Us.key('@'));
+        assert_eq!(Some((Key::Apostrophe, true)), Layout::Uk.key('@'));
+        assert_eq!(None, Layout::Us.key('£'));
+    }
+}
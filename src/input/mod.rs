@@ -1,10 +1,11 @@
 mod axis;
 mod btn;
 mod key;
+mod layout;
 
-use std::{fmt::Display, str::FromStr};
+use std::{collections::HashSet, fmt::Display, str::FromStr};
 
-pub use self::{axis::*, btn::Btn, key::Key};
+pub use self::{axis::*, btn::Btn, key::Key, layout::Layout};
 
 #[macro_export(local_inner_macros)]
 macro_rules! __input_enum_internal {
@@ -75,7 +76,7 @@ macro_rules! input_enum {
     };
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Input {
     Key(Key),
     Btn(Btn),
@@ -133,6 +134,84 @@ impl From<evdev::Key> for Input {
     }
 }
 
+/// A trigger input preceded by any number of modifier inputs that have to be
+/// held for the chord to match, e.g. `key:leftctrl+key:c`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Chord {
+    pub modifiers: Vec<Input>,
+    pub trigger: Input,
+    /// Inputs pressed one after another before the trigger, starting with a
+    /// leader key, e.g. `["key:capslock", "key:g", "key:s"]`
+    pub sequence: Vec<Input>,
+}
+impl Chord {
+    /// A sequence of inputs, the last of which triggers it
+    pub fn sequence(mut steps: Vec<Input>) -> Option<Self> {
+        let trigger = steps.pop()?;
+
+        Some(Self {
+            modifiers: vec![],
+            trigger,
+            sequence: steps,
+        })
+    }
+
+    /// Every input of a sequence in order, including the trigger
+    pub fn steps(&self) -> impl Iterator<Item = Input> + '_ {
+        self.sequence
+            .iter()
+            .copied()
+            .chain(std::iter::once(self.trigger))
+    }
+
+    pub fn is_held(&self, held: &HashSet<Input>) -> bool {
+        self.modifiers
+            .iter()
+            .all(|modifier| held.contains(modifier))
+    }
+}
+impl From<Input> for Chord {
+    fn from(trigger: Input) -> Self {
+        Self {
+            modifiers: vec![],
+            trigger,
+            sequence: vec![],
+        }
+    }
+}
+impl FromStr for Chord {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut inputs = s
+            .split('+')
+            .map(|input| input.trim().parse())
+            .collect::<Result<Vec<Input>, ()>>()?;
+
+        let trigger = inputs.pop().ok_or(())?;
+
+        // Only inputs that can be held down can act as modifiers
+        if !inputs.iter().all(Input::is_toggle) {
+            return Err(());
+        }
+
+        Ok(Self {
+            modifiers: inputs,
+            trigger,
+            sequence: vec![],
+        })
+    }
+}
+impl Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier)?;
+        }
+
+        write!(f, "{}", self.trigger)
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum InputEvent {
     Key(Key, InputState),
@@ -251,4 +330,26 @@ mod tests {
         assert_eq!("key:1", &one.to_string());
         assert_eq!("btn:north", &north.to_string());
     }
+
+    #[test]
+    fn chord_from_str() {
+        let copy = Chord {
+            modifiers: vec![Input::Key(Key::Leftctrl)],
+            trigger: Input::Key(Key::C),
+            sequence: vec![],
+        };
+
+        assert_eq!(Ok(copy.clone()), "key:leftctrl+key:c".parse());
+        assert_eq!(Ok(copy), "key:leftctrl + key:c".parse());
+        assert_eq!(Ok(Chord::from(Input::Btn(Btn::North))), "btn:north".parse());
+        assert_eq!(Err(()), "abs_axis:x+key:c".parse::<Chord>());
+        assert_eq!(Err(()), "key:leftctrl+".parse::<Chord>());
+        assert_eq!(
+            "key:leftctrl+key:leftalt+key:t",
+            "key:leftctrl+key:leftalt+key:t"
+                .parse::<Chord>()
+                .unwrap()
+                .to_string()
+        );
+    }
 }
@@ -1,5 +1,4 @@
 #![feature(file_create_new)]
-#![feature(iter_collect_into)]
 #![feature(if_let_guard)]
 #![feature(let_chains)]
 
@@ -8,16 +7,17 @@ mod config;
 mod device;
 mod events;
 mod input;
+mod timer;
 
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use action::ActionExecutor;
 use config::Config;
-use device::{events::DeviceEventWatch, open_devices, path_in_devices, DeviceIdCombo};
+use device::{assign_devices, events::DeviceEventWatch, open_devices, DeviceIdCombo, DeviceSlot};
 use evdev::Device;
 use events::{event_pipeline, Event};
 
-use crate::device::{DeviceAccessor, DeviceId};
+use crate::device::DeviceId;
 
 #[cfg(feature = "tokio")]
 use tokio::{join, runtime};
@@ -28,63 +28,111 @@ mod mio_channel;
 mod thread;
 
 struct State {
-    accessors: Vec<DeviceAccessor>,
+    devices: Vec<DeviceSlot>,
+    /// The devices to grab
+    grabbed: Vec<DeviceId>,
+    /// The id each watched device node was assigned, so that the player
+    /// slots in use are not assigned again. Ids are reserved as soon as they
+    /// are assigned, before the device is added.
+    watched: HashMap<String, DeviceId>,
     device_event_watch: DeviceEventWatch,
 }
 impl State {
     pub fn new(config: &Config, device_event_watch: DeviceEventWatch) -> Self {
-        let accessors: Vec<DeviceAccessor> = config
-            .devices
-            .iter()
-            .map(|dev| dev.accessor.clone())
-            .collect();
+        let devices: Vec<DeviceSlot> = config.devices.iter().map(|dev| dev.slot()).collect();
 
         Self {
-            accessors,
+            devices,
+            grabbed: grabbed_devices(config),
+            watched: HashMap::new(),
             device_event_watch,
         }
     }
 
-    #[cfg(feature = "tokio")]
-    pub async fn watch_devices(&self) {
-        self.device_event_watch
-            .watch(open_devices(&self.accessors))
-            .await;
+    fn with_grabs(&self, devices: Vec<DeviceIdCombo>) -> Vec<DeviceIdCombo> {
+        devices
+            .into_iter()
+            .map(|device| {
+                let grab = self.grabbed.iter().any(|id| id == device.section());
+
+                device.with_grab(grab)
+            })
+            .collect()
     }
 
-    #[cfg(not(feature = "tokio"))]
-    pub fn watch_devices(&self) {
-        self.device_event_watch.watch(open_devices(&self.accessors));
+    /// Reserves the ids assigned to the devices about to be watched, so that
+    /// devices plugged in before they are added get other ids
+    fn reserve(&mut self, devices: &[DeviceIdCombo]) {
+        for device in devices {
+            self.watched
+                .insert(device.instance().to_string(), device.id().to_string());
+        }
     }
 
-    #[cfg(feature = "tokio")]
-    pub async fn add_devices_to_watch(&self, added: Vec<PathBuf>) {
+    fn open_devices(&mut self) -> Vec<DeviceIdCombo> {
+        let devices = self.with_grabs(open_devices(&self.devices));
+        self.reserve(&devices);
+
+        devices
+    }
+
+    fn added_devices(&mut self, added: Vec<PathBuf>) -> Vec<DeviceIdCombo> {
         let added = added
             .into_iter()
             .filter_map(|path| {
                 let device = Device::open(&path).ok()?;
 
-                path_in_devices(path, &device, &self.accessors)
-                    .map(|accessor| DeviceIdCombo::from_accessor(accessor.clone(), device))
+                Some((path, device))
             })
             .collect();
 
+        let taken = self.watched.values().cloned().collect();
+
+        let devices = self.with_grabs(assign_devices(added, &self.devices, &taken));
+        self.reserve(&devices);
+
+        devices
+    }
+
+    pub fn device_added(&mut self, instance: String, id: DeviceId) {
+        self.watched.insert(instance, id);
+    }
+
+    pub fn device_removed(&mut self, instance: &str, id: &str) {
+        // The device node may have been assigned again since
+        if self
+            .watched
+            .get(instance)
+            .is_some_and(|watched| watched == id)
+        {
+            self.watched.remove(instance);
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn watch_devices(&mut self) {
+        let devices = self.open_devices();
+        self.device_event_watch.watch(devices).await;
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    pub fn watch_devices(&mut self) {
+        let devices = self.open_devices();
+        self.device_event_watch.watch(devices);
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn add_devices_to_watch(&mut self, added: Vec<PathBuf>) {
+        let added = self.added_devices(added);
+
         // The removed devices are automatically removed by the event stream map
         // when their event stream returns an error
         self.device_event_watch.watch(added).await;
     }
 
     #[cfg(not(feature = "tokio"))]
-    pub fn add_devices_to_watch(&self, added: Vec<PathBuf>) {
-        let added = added
-            .into_iter()
-            .filter_map(|path| {
-                let device = Device::open(&path).ok()?;
-
-                path_in_devices(path, &device, &self.accessors)
-                    .map(|accessor| DeviceIdCombo::from_accessor(accessor.clone(), device))
-            })
-            .collect();
+    pub fn add_devices_to_watch(&mut self, added: Vec<PathBuf>) {
+        let added = self.added_devices(added);
 
         // The removed devices are automatically removed by the event stream map
         // when their event stream returns an error
@@ -93,42 +141,45 @@ impl State {
 
     #[cfg(feature = "tokio")]
     pub async fn update_config(&mut self, new_config: &Config) {
-        let removed: Vec<DeviceId> = self
-            .accessors
-            .iter()
-            .map(|accessor| accessor.to_string())
-            .collect();
+        let removed = self.watched_ids();
 
-        self.device_event_watch.unwatch(removed);
+        // The devices are released before they are grabbed again
+        self.device_event_watch.unwatch(removed).await;
 
-        self.accessors = new_config
-            .devices
-            .iter()
-            .map(|dev| dev.accessor.clone())
-            .collect();
+        self.devices = new_config.devices.iter().map(|dev| dev.slot()).collect();
+        self.grabbed = grabbed_devices(new_config);
+        self.watched.clear();
 
-        self.device_event_watch
-            .watch(open_devices(&self.accessors))
-            .await;
+        let devices = self.open_devices();
+        self.device_event_watch.watch(devices).await;
     }
 
     #[cfg(not(feature = "tokio"))]
     pub fn update_config(&mut self, new_config: &Config) {
-        let removed: Vec<DeviceId> = self
-            .accessors
-            .iter()
-            .map(|accessor| accessor.to_string())
-            .collect();
+        let removed = self.watched_ids();
 
         self.device_event_watch.unwatch(removed);
 
-        self.accessors = new_config
-            .devices
-            .iter()
-            .map(|dev| dev.accessor.clone())
-            .collect();
+        self.devices = new_config.devices.iter().map(|dev| dev.slot()).collect();
+        self.grabbed = grabbed_devices(new_config);
+        self.watched.clear();
 
-        self.device_event_watch.watch(open_devices(&self.accessors));
+        let devices = self.open_devices();
+        self.device_event_watch.watch(devices);
+    }
+
+    /// The ids of the configured devices and of every watched device, which
+    /// differ for devices matched by the same configured device
+    fn watched_ids(&self) -> Vec<DeviceId> {
+        let mut ids: Vec<DeviceId> = self.devices.iter().map(|dev| dev.id()).collect();
+
+        for id in self.watched.values() {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+
+        ids
     }
 
     pub fn into_device_event_watch(self) -> DeviceEventWatch {
@@ -136,6 +187,15 @@ impl State {
     }
 }
 
+fn grabbed_devices(config: &Config) -> Vec<DeviceId> {
+    config
+        .devices
+        .iter()
+        .filter(|dev| dev.grab)
+        .map(|dev| dev.id())
+        .collect()
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (config_path, config) = config::load()?;
 
@@ -152,11 +212,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let device_watch_handle = device::watch(event_pipeline_sender.clone()).await?;
 
             let device_event_watcher = DeviceEventWatch::new(event_pipeline_sender.clone())?;
+            let (scheduler, timer_handle) = timer::scheduler(event_pipeline_sender.clone());
 
             let mut state = State::new(&config, device_event_watcher);
             state.watch_devices().await;
 
-            let mut action_executor = ActionExecutor::from_config(config);
+            let mut action_executor = ActionExecutor::from_config(config, scheduler);
 
             while let Some(event) = event_pipeline_receiver.recv().await {
                 match event {
@@ -172,10 +233,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                         action_executor.update_config(config);
                     }
-                    Event::DeviceEvent(_) => {}
+                    Event::DeviceEvent(event) => action_executor.handle_event(event),
                     Event::DeviceInput(input) => {
                         action_executor.handle_input(input);
                     }
+                    Event::DeviceAdded {
+                        id,
+                        section,
+                        instance,
+                        abs_info,
+                        passthrough,
+                    } => {
+                        state.device_added(instance, id.clone());
+
+                        action_executor.add_device(id, section, abs_info, passthrough);
+                    }
+                    Event::DeviceRemoved { id, instance } => {
+                        state.device_removed(&instance, &id);
+
+                        action_executor.remove_device(&id);
+                    }
+                    Event::Timer(id) => {
+                        action_executor.handle_timer(id);
+                    }
                 }
             }
 
@@ -185,6 +265,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 config_watch_handle,
                 device_watch_handle,
                 device_event_watch_handle,
+                timer_handle,
             );
 
             Result::<(), Box<dyn std::error::Error>>::Ok(())
@@ -197,11 +278,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let device_watch_handle = device::watch(event_pipeline_sender.clone())?;
 
         let device_event_watcher = DeviceEventWatch::new(event_pipeline_sender.clone())?;
+        let (scheduler, timer_handle) = timer::scheduler(event_pipeline_sender.clone());
 
         let mut state = State::new(&config, device_event_watcher);
         state.watch_devices();
 
-        let mut action_executor = ActionExecutor::from_config(config);
+        let mut action_executor = ActionExecutor::from_config(config, scheduler);
 
         while let Ok(event) = event_pipeline_receiver.recv() {
             match event {
@@ -215,16 +297,36 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                     action_executor.update_config(config);
                 }
-                Event::DeviceEvent(_) => {}
+                Event::DeviceEvent(event) => action_executor.handle_event(event),
                 Event::DeviceInput(input) => {
                     action_executor.handle_input(input);
                 }
+                Event::DeviceAdded {
+                    id,
+                    section,
+                    instance,
+                    abs_info,
+                    passthrough,
+                } => {
+                    state.device_added(instance, id.clone());
+
+                    action_executor.add_device(id, section, abs_info, passthrough);
+                }
+                Event::DeviceRemoved { id, instance } => {
+                    state.device_removed(&instance, &id);
+
+                    action_executor.remove_device(&id);
+                }
+                Event::Timer(id) => {
+                    action_executor.handle_timer(id);
+                }
             }
         }
 
         let _ = config_watch_handle.join();
         let _ = device_watch_handle.join();
         let _ = state.into_device_event_watch().handle().join();
+        let _ = timer_handle.join();
     }
 
     Ok(())
@@ -0,0 +1,270 @@
+//! Timers that are delivered through the event pipeline, allowing the
+//! [`ActionExecutor`](crate::action::ActionExecutor) to act on the passing of
+//! time as well as on device inputs
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    time::{Duration, Instant},
+};
+
+use crate::events::{Event, EventPipelineSender};
+
+#[cfg(feature = "tokio")]
+use tokio::{
+    sync::mpsc::{
+        unbounded_channel as channel, UnboundedReceiver as Receiver, UnboundedSender as Sender,
+    },
+    task::JoinHandle,
+};
+
+#[cfg(not(feature = "tokio"))]
+use std::{
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread::JoinHandle,
+};
+
+#[cfg(not(feature = "tokio"))]
+use crate::thread;
+
+pub type TimerId = u64;
+
+#[derive(Debug)]
+enum TimerUpdate {
+    Start {
+        id: TimerId,
+        deadline: Instant,
+        period: Option<Duration>,
+    },
+    Cancel(TimerId),
+}
+
+/// Handle used to start and cancel timers. When a timer expires an
+/// [`Event::Timer`] carrying its id is sent through the event pipeline.
+pub struct Scheduler {
+    next_id: TimerId,
+    timer_update_channel: Sender<TimerUpdate>,
+}
+impl Scheduler {
+    pub fn schedule(&mut self, delay: Duration) -> TimerId {
+        self.start(delay, None)
+    }
+
+    /// Starts a timer that expires every `period` until it is cancelled
+    pub fn schedule_periodic(&mut self, period: Duration) -> TimerId {
+        let period = period.max(Duration::from_millis(1));
+
+        self.start(period, Some(period))
+    }
+
+    fn start(&mut self, delay: Duration, period: Option<Duration>) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let _ = self.timer_update_channel.send(TimerUpdate::Start {
+            id,
+            deadline: Instant::now() + delay,
+            period,
+        });
+
+        id
+    }
+
+    /// Cancels a timer. A timer that has already expired may still have its
+    /// event waiting in the event pipeline, so unknown ids have to be ignored
+    /// by the receiver.
+    pub fn cancel(&self, id: TimerId) {
+        let _ = self.timer_update_channel.send(TimerUpdate::Cancel(id));
+    }
+}
+
+#[derive(Default)]
+struct Timers {
+    deadlines: BinaryHeap<Reverse<(Instant, TimerId)>>,
+    /// The timers that have not expired or been cancelled, with the period
+    /// of the periodic ones
+    active: HashMap<TimerId, Option<Duration>>,
+}
+impl Timers {
+    fn update(&mut self, update: TimerUpdate) {
+        match update {
+            TimerUpdate::Start {
+                id,
+                deadline,
+                period,
+            } => {
+                self.deadlines.push(Reverse((deadline, id)));
+                self.active.insert(id, period);
+            }
+            TimerUpdate::Cancel(id) => {
+                self.active.remove(&id);
+            }
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines
+            .peek()
+            .map(|Reverse((deadline, _))| *deadline)
+    }
+
+    /// Sends an event for every expired timer, returns false if the event
+    /// pipeline has been closed
+    fn expire(&mut self, event_pipeline: &EventPipelineSender) -> bool {
+        let now = Instant::now();
+
+        while let Some(&Reverse((deadline, id))) = self.deadlines.peek() {
+            if deadline > now {
+                break;
+            }
+
+            self.deadlines.pop();
+
+            let Some(&period) = self.active.get(&id) else {
+                continue;
+            };
+
+            match period {
+                Some(period) => {
+                    let mut next = deadline + period;
+
+                    // Expirations missed while the thread was busy are
+                    // skipped rather than sent in a burst
+                    if next <= now {
+                        next = now + period;
+                    }
+
+                    self.deadlines.push(Reverse((next, id)));
+                }
+                None => {
+                    self.active.remove(&id);
+                }
+            }
+
+            if event_pipeline.send(Event::Timer(id)).is_err() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub fn scheduler(event_pipeline: EventPipelineSender) -> (Scheduler, JoinHandle<()>) {
+    let (tx, rx) = channel();
+
+    let timer_handle = tokio::spawn(watch(event_pipeline, rx));
+
+    let scheduler = Scheduler {
+        next_id: 0,
+        timer_update_channel: tx,
+    };
+
+    (scheduler, timer_handle)
+}
+
+#[cfg(feature = "tokio")]
+async fn watch(event_pipeline: EventPipelineSender, mut rx: Receiver<TimerUpdate>) {
+    use tokio::time::{sleep_until, Instant as TokioInstant};
+
+    let mut timers = Timers::default();
+
+    loop {
+        let next_deadline = timers.next_deadline();
+
+        tokio::select! {
+            update = rx.recv() => match update {
+                Some(update) => timers.update(update),
+                None => break,
+            },
+            _ = sleep_until(TokioInstant::from_std(next_deadline.unwrap_or_else(Instant::now))),
+                if next_deadline.is_some() => {}
+        }
+
+        if !timers.expire(&event_pipeline) {
+            break;
+        }
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+pub fn scheduler(event_pipeline: EventPipelineSender) -> (Scheduler, JoinHandle<()>) {
+    let (tx, rx) = channel();
+
+    let timer_handle = thread::spawn_named("timer", move || watch(event_pipeline, rx));
+
+    let scheduler = Scheduler {
+        next_id: 0,
+        timer_update_channel: tx,
+    };
+
+    (scheduler, timer_handle)
+}
+
+#[cfg(not(feature = "tokio"))]
+fn watch(event_pipeline: EventPipelineSender, rx: Receiver<TimerUpdate>) {
+    let mut timers = Timers::default();
+
+    loop {
+        let update = match timers.next_deadline() {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match update {
+            Ok(update) => timers.update(update),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if !timers.expire(&event_pipeline) {
+            break;
+        }
+    }
+}
+
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn periodic_timer_is_rescheduled() {
+        #[allow(unused_mut)]
+        let (tx, mut rx) = crate::events::event_pipeline();
+
+        let mut timers = Timers::default();
+
+        let now = Instant::now();
+        let period = Duration::from_secs(60);
+
+        timers.update(TimerUpdate::Start {
+            id: 0,
+            deadline: now,
+            period: Some(period),
+        });
+        timers.update(TimerUpdate::Start {
+            id: 1,
+            deadline: now,
+            period: None,
+        });
+
+        assert!(timers.expire(&tx));
+
+        let mut expired = vec![];
+        while let Ok(Event::Timer(id)) = rx.try_recv() {
+            expired.push(id);
+        }
+        expired.sort();
+
+        assert_eq!(vec![0, 1], expired);
+        assert!(timers
+            .next_deadline()
+            .is_some_and(|deadline| deadline > now));
+
+        timers.update(TimerUpdate::Cancel(0));
+
+        assert!(timers.expire(&tx));
+        assert!(timers.active.is_empty());
+    }
+}